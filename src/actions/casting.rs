@@ -0,0 +1,231 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::rc::{Rc, Weak};
+
+use utils::*;
+use world::*;
+use creatures::Creature;
+use spells::*;
+use events::Event;
+use super::*;
+
+/// Position the spell is aimed at
+fn target_position(caster: &Creature, target: &SpellTarget)
+                   -> std::result::Result<Position, ActionError> {
+    match *target {
+        SpellTarget::Caster => Ok(caster.position()),
+        SpellTarget::Creature(ref creature) => match creature.upgrade() {
+            Some(ref creature) if creature.borrow().is_alive() =>
+                Ok(creature.borrow().position()),
+            _ => Err(ActionError::InvalidTarget),
+        },
+        SpellTarget::Tile(position) | SpellTarget::Area(position) => Ok(position),
+    }
+}
+
+pub(super) fn is_cast_valid(
+    world: &World,
+    creature: &Weak<CreatureRef>,
+    spell: &Spell,
+    target: &SpellTarget) -> Result {
+    let caster = creature.upgrade().ok_or(ActionError::SubjectIsDead)?;
+    let caster = caster.borrow();
+    if !caster.can_cast(spell) {
+        return Err(ActionError::SpellIsLocked)
+    }
+    if caster.mana().current() < spell.mana_cost {
+        return Err(ActionError::NotEnoughMana {
+            required: spell.mana_cost,
+            available: caster.mana().current(),
+        })
+    }
+    if !target.matches(spell.targeting) {
+        return Err(ActionError::InvalidTarget)
+    }
+
+    let position = target_position(&caster, target)?;
+    if caster.position().distance(&position) > spell.range {
+        return Err(ActionError::TargetOutOfRange { target: position, range: spell.range })
+    }
+
+    let map = world.get_level(position.level).borrow();
+    let Map(ref tiles) = *map;
+    if tiles.len() <= position.x || tiles[0].len() <= position.y {
+        return Err(ActionError::OutOfBounds {
+            position: Some(position),
+            width: tiles.len(),
+            height: tiles[0].len(),
+        })
+    }
+    if spell.effect == SpellEffect::Teleport {
+        let tile = map.get_by_pos(position);
+        if let Some(ref creature) = tile.creature {
+            return Err(ActionError::TileIsOccupied(creature.clone()))
+        }
+        if !tile.is_passable() {
+            return Err(ActionError::TileIsImpassable(position))
+        }
+    }
+    Ok(())
+}
+
+/// Creatures affected by the spell, caster is never hurt by its own area spells
+fn affected_creatures(
+    world: &World,
+    caster: &Rc<CreatureRef>,
+    spell: &Spell,
+    target: &SpellTarget) -> Vec<Rc<CreatureRef>> {
+    match (target, spell.targeting) {
+        (SpellTarget::Caster, _) => vec![caster.clone()],
+        (SpellTarget::Creature(creature), _) => creature.upgrade().into_iter().collect(),
+        (SpellTarget::Tile(position), _) => world.creatures_around(*position, 0),
+        (SpellTarget::Area(position), Targeting::Area { radius }) =>
+            world.creatures_around(*position, radius)
+                .into_iter()
+                .filter(|creature| !creature.identical(caster))
+                .collect(),
+        (SpellTarget::Area(_), _) => vec![],
+    }
+}
+
+pub(super) fn cast(
+    world: &mut World,
+    creature: &Weak<CreatureRef>,
+    spell: &Spell,
+    target: &SpellTarget) -> Result {
+    is_cast_valid(world, creature, spell, target)?;
+    let caster = creature.upgrade().unwrap();
+    caster.borrow_mut().spend_mana(spell.mana_cost);
+
+    match spell.effect {
+        SpellEffect::Damage(damage) =>
+            for target in affected_creatures(world, &caster, spell, target) {
+                world.damage_creature(&target, damage, Some(&caster));
+            },
+        SpellEffect::Heal(amount) =>
+            for target in affected_creatures(world, &caster, spell, target) {
+                let amount = target.borrow_mut().heal(amount);
+                world.log_event(Event::Healed { creature: Rc::downgrade(&target), amount });
+            },
+        SpellEffect::Inflict(effect) =>
+            for target in affected_creatures(world, &caster, spell, target) {
+                world.apply_status(&target, effect);
+            },
+        SpellEffect::Teleport => {
+            let position = target_position(&caster.borrow(), target)?;
+            world.relocate_creature(&caster, position);
+        },
+    }
+    if spell.demonicity_gain > 0 {
+        world.change_demonicity(&caster, spell.demonicity_gain as i32);
+    }
+    Ok(())
+}
+
+pub(super) fn cast_cost(creature: &Weak<CreatureRef>, spell: &Spell) -> u32 {
+    match creature.upgrade() {
+        Some(_) => spell.time_cost,
+        None => 0,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (World, Weak<CreatureRef>, Position) {
+        let world = World::new();
+        let character = world.main_character();
+        let position = character.upgrade().unwrap().borrow().position();
+        (world, character, position)
+    }
+
+    #[test]
+    fn cast_spends_mana() {
+        let (mut world, character, position) = setup();
+        let target = SpellTarget::Tile((position + Direction::Right).unwrap());
+        let spell = Spell::shadow_step();
+        cast(&mut world, &character, &spell, &target).unwrap();
+
+        let character = character.upgrade().unwrap();
+        let character = character.borrow();
+        assert_eq!(character.mana().current(), character.mana().max() - spell.mana_cost);
+        assert_eq!(character.position(), (position + Direction::Right).unwrap());
+    }
+
+    #[test]
+    fn not_enough_mana() {
+        let (world, character, _) = setup();
+        let mut spell = Spell::shadow_step();
+        spell.mana_cost = 1000;
+        match is_cast_valid(&world, &character, &spell, &SpellTarget::Caster) {
+            Err(ActionError::NotEnoughMana { required: 1000, .. }) => (),
+            _ => panic!("Spell must require more mana than caster has"),
+        }
+    }
+
+    #[test]
+    fn target_out_of_range() {
+        let (world, character, position) = setup();
+        let target = SpellTarget::Tile(Position { x: position.x + 10, ..position });
+        match is_cast_valid(&world, &character, &Spell::shadow_step(), &target) {
+            Err(ActionError::TargetOutOfRange { .. }) => (),
+            _ => panic!("Target must be out of range"),
+        }
+    }
+
+    #[test]
+    fn locked_spell() {
+        let (world, character, position) = setup();
+        let target = SpellTarget::Area(position);
+        match is_cast_valid(&world, &character, &Spell::hellfire(), &target) {
+            Err(ActionError::SpellIsLocked) => (),
+            _ => panic!("Hellfire must be locked for not corrupted demon"),
+        }
+    }
+
+    #[test]
+    fn dark_spell_corrupts() {
+        let (mut world, character, position) = setup();
+        let target = SpellTarget::Tile((position + Direction::Right).unwrap());
+        cast(&mut world, &character, &Spell::shadow_step(), &target).unwrap();
+        assert_eq!(character.upgrade().unwrap().borrow().demonicity(),
+                   Some(Spell::shadow_step().demonicity_gain));
+    }
+
+    #[test]
+    fn wrong_target_kind() {
+        let (world, character, _) = setup();
+        match is_cast_valid(&world, &character, &Spell::infernal_bolt(), &SpellTarget::Caster) {
+            Err(ActionError::InvalidTarget) => (),
+            _ => panic!("Bolt can't be cast on caster"),
+        }
+        character.upgrade().unwrap().borrow_mut().change_demonicity(50);
+        match is_cast_valid(&world, &character, &Spell::hellfire(), &SpellTarget::Caster) {
+            Err(ActionError::InvalidTarget) => (),
+            _ => panic!("Area spell can't be cast on caster"),
+        }
+    }
+}
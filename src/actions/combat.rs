@@ -0,0 +1,204 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::cmp;
+use std::rc::{Rc, Weak};
+
+use utils::*;
+use world::*;
+use creatures::Creature;
+use rng::Rng;
+use super::*;
+
+const ATTACK_COST: u32 = 100;
+
+/// Returns living creature standing next to the attacker in given direction
+fn target(
+    world: &World,
+    creature: &Rc<CreatureRef>,
+    direction: Direction) -> std::result::Result<Rc<CreatureRef>, ActionError> {
+    let creature = creature.borrow();
+    let map = world.get_level(creature.position().level).borrow();
+    let position = adjacent_position(&map, creature.position(), direction)?;
+    match map.get_by_pos(position).creature.as_ref().and_then(|target| target.upgrade()) {
+        Some(target) if target.borrow().is_alive() => Ok(target),
+        _ => Err(ActionError::NoTarget(position)),
+    }
+}
+
+/// Returns creature in given direction only if it is hostile to the given one,
+/// used to turn moves into attacks
+pub(super) fn hostile_target(
+    world: &World,
+    creature: &Rc<CreatureRef>,
+    direction: Direction) -> Option<Rc<CreatureRef>> {
+    target(world, creature, direction)
+        .ok()
+        .filter(|target| creature.borrow().is_hostile_to(&target.borrow()))
+}
+
+pub(super) fn is_attack_valid(
+    world: &World,
+    creature: &Weak<CreatureRef>,
+    direction: Direction) -> Result {
+    let creature = creature.upgrade().ok_or(ActionError::SubjectIsDead)?;
+    let target = target(world, &creature, direction)?;
+    let position = creature.borrow().position();
+    if !world.corner_cutting() &&
+        world.get_level(position.level).borrow().cuts_corner(position, direction) {
+        return Err(ActionError::CutsCorner(target.borrow().position()))
+    }
+    Ok(())
+}
+
+pub(super) fn attack(
+    world: &mut World,
+    creature: &Weak<CreatureRef>,
+    direction: Direction) -> Result {
+    is_attack_valid(world, creature, direction)?;
+    let attacker = creature.upgrade().unwrap();
+    let target = target(world, &attacker, direction)?;
+    let damage = damage(&attacker.borrow(), &target.borrow(), &mut world.combat_rng());
+    world.damage_creature(&target, damage, Some(&attacker));
+    Ok(())
+}
+
+pub(super) fn attack_cost(creature: &Weak<CreatureRef>) -> u32 {
+    match creature.upgrade() {
+        Some(_) => ATTACK_COST,
+        None => 0,
+    }
+}
+
+/// Attack varies by a quarter either way, every successful hit deals at
+/// least one point of damage
+fn damage(attacker: &Creature, target: &Creature, rng: &mut Rng) -> u32 {
+    let attack = attacker.attack() as usize;
+    let attack = attack - attack / 4 + rng.range(0, attack / 2 + 1);
+    cmp::max(1, (attack as u32).saturating_sub(target.defense()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use creatures::Profession;
+    use map::tiles::{Tile, TileType};
+
+    fn setup() -> (World, Weak<CreatureRef>) {
+        let mut world = World::new();
+        let position = world.main_character().upgrade().unwrap().borrow().position();
+        let demon = Rc::new(RefCell::new(Creature::demon(
+            String::from("Imp"), 5, 0, (position + Direction::Right).unwrap(), 0)));
+        let imp = Rc::downgrade(&demon);
+        world.add_creature(demon);
+        (world, imp)
+    }
+
+    #[test]
+    fn attack_until_death() {
+        let (mut world, imp) = setup();
+        let character = world.main_character();
+        let position = imp.upgrade().unwrap().borrow().position();
+
+        attack(&mut world, &character, Direction::Right).unwrap();
+        assert!(imp.upgrade().unwrap().borrow().health().current() < 5);
+        attack(&mut world, &character, Direction::Right).unwrap();
+
+        assert!(imp.upgrade().is_none(), "Dead creature must be removed from the world");
+        let map = world.get_level(0).borrow();
+        assert!(map.get_by_pos(position).creature.is_none());
+        assert_eq!(map.get_by_pos(position).corpses.len(), 1);
+    }
+
+    #[test]
+    fn move_into_hostile_attacks() {
+        let mut world = World::new();
+        let character = world.main_character();
+        let position = character.upgrade().unwrap().borrow().position();
+        let guard = Rc::new(RefCell::new(Creature::human(
+            String::from("Guard"), 10, 0, (position + Direction::Right).unwrap(),
+            Profession::Guard)));
+        world.add_creature(guard.clone());
+
+        Action::Move(character.clone(), Direction::Right).apply(&mut world).unwrap();
+        assert_eq!(character.upgrade().unwrap().borrow().position(), position);
+        assert!(guard.borrow().health().current() < 10);
+    }
+
+    #[test]
+    fn damage_varies() {
+        let (world, imp) = setup();
+        let character = world.main_character().upgrade().unwrap();
+        let imp = imp.upgrade().unwrap();
+        let mut rng = Rng::new(0);
+        let damages: Vec<_> = (0..100)
+            .map(|_| damage(&character.borrow(), &imp.borrow(), &mut rng))
+            .collect();
+        // attack of 5 varies from 4 to 6, minus defense of 1
+        assert!(damages.iter().all(|damage| (3..6).contains(damage)));
+        assert!(damages.contains(&3) && damages.contains(&5));
+    }
+
+    #[test]
+    fn move_into_friendly_blocked() {
+        let (world, _) = setup();
+        match Action::Move(world.main_character(), Direction::Right).is_valid(&world) {
+            Err(ActionError::TileIsOccupied(_)) => (),
+            _ => panic!("Demons must not attack each other on move"),
+        }
+    }
+
+    #[test]
+    fn diagonal_attack_past_corner() {
+        let mut world = World::new();
+        let character = world.main_character();
+        let position = character.upgrade().unwrap().borrow().position();
+        let guard = Rc::new(RefCell::new(Creature::human(
+            String::from("Guard"), 10, 0, (position + Direction::DownRight).unwrap(),
+            Profession::Guard)));
+        world.add_creature(guard);
+        let wall = (position + Direction::Right).unwrap();
+        world.get_level(0).borrow_mut().0[wall.x][wall.y] = Tile::new(TileType::Wall);
+        assert!(is_attack_valid(&world, &character, Direction::DownRight).is_ok());
+
+        world.set_corner_cutting(false);
+        for action in [Action::Attack(character.clone(), Direction::DownRight),
+                       Action::Move(character.clone(), Direction::DownRight)].iter() {
+            match action.is_valid(&world) {
+                Err(ActionError::CutsCorner(_)) => (),
+                _ => panic!("Attack past the wall corner must be forbidden"),
+            }
+        }
+    }
+
+    #[test]
+    fn attack_empty_tile() {
+        let (world, _) = setup();
+        match is_attack_valid(&world, &world.main_character(), Direction::Left) {
+            Err(ActionError::NoTarget(_)) => (),
+            _ => panic!("Attack of the empty tile must be invalid"),
+        }
+    }
+}
@@ -0,0 +1,285 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::rc::{Rc, Weak};
+
+use utils::*;
+use world::*;
+use map::tiles::TileType;
+use super::*;
+
+const DOOR_COST: u32 = 100;
+/// Attack plus the roll of a six-sided die must exceed it to break the door
+const BASH_DIFFICULTY: u32 = 11;
+
+/// Position and type of the tile next to the creature
+fn adjacent_tile(
+    world: &World,
+    creature: &Rc<CreatureRef>,
+    direction: Direction) -> std::result::Result<(Position, TileType), ActionError> {
+    let position = creature.borrow().position();
+    let map = world.get_level(position.level).borrow();
+    let position = adjacent_position(&map, position, direction)?;
+    Ok((position, map.get_by_pos(position).tile_type.clone()))
+}
+
+fn set_door(world: &mut World, position: Position, tile_type: TileType) {
+    world.get_level(position.level).borrow_mut().0[position.x][position.y].tile_type = tile_type;
+    world.tiles_changed(position.level);
+}
+
+pub(super) fn is_open_valid(
+    world: &World,
+    creature: &Weak<CreatureRef>,
+    direction: Direction) -> Result {
+    let creature = creature.upgrade().ok_or(ActionError::SubjectIsDead)?;
+    match adjacent_tile(world, &creature, direction)? {
+        (_, TileType::Door { closed: true, locked: false, .. }) => Ok(()),
+        (_, TileType::Door { closed: true, lock: Some(lock), .. })
+            if creature.borrow().inventory().has_key(lock) => Ok(()),
+        (position, TileType::Door { closed: true, .. }) =>
+            Err(ActionError::DoorIsLocked(position)),
+        (position, _) => Err(ActionError::NoClosedDoor(position)),
+    }
+}
+
+pub(super) fn open(
+    world: &mut World,
+    creature: &Weak<CreatureRef>,
+    direction: Direction) -> Result {
+    is_open_valid(world, creature, direction)?;
+    let (position, tile_type) = adjacent_tile(world, &creature.upgrade().unwrap(), direction)?;
+    if let TileType::Door { lock, .. } = tile_type {
+        set_door(world, position, TileType::Door { closed: false, locked: false, lock });
+    }
+    Ok(())
+}
+
+pub(super) fn is_close_valid(
+    world: &World,
+    creature: &Weak<CreatureRef>,
+    direction: Direction) -> Result {
+    let creature = creature.upgrade().ok_or(ActionError::SubjectIsDead)?;
+    match adjacent_tile(world, &creature, direction)? {
+        (position, TileType::Door { closed: false, .. }) => {
+            let map = world.get_level(position.level).borrow();
+            match map.get_by_pos(position).creature {
+                Some(ref other) => Err(ActionError::TileIsOccupied(other.clone())),
+                None => Ok(()),
+            }
+        },
+        (position, _) => Err(ActionError::NoOpenDoor(position)),
+    }
+}
+
+pub(super) fn close(
+    world: &mut World,
+    creature: &Weak<CreatureRef>,
+    direction: Direction) -> Result {
+    is_close_valid(world, creature, direction)?;
+    let (position, tile_type) = adjacent_tile(world, &creature.upgrade().unwrap(), direction)?;
+    if let TileType::Door { lock, .. } = tile_type {
+        set_door(world, position, TileType::Door { closed: true, locked: false, lock });
+    }
+    Ok(())
+}
+
+pub(super) fn is_lock_valid(
+    world: &World,
+    creature: &Weak<CreatureRef>,
+    direction: Direction) -> Result {
+    let creature = creature.upgrade().ok_or(ActionError::SubjectIsDead)?;
+    match adjacent_tile(world, &creature, direction)? {
+        (position, TileType::Door { closed: true, locked: true, .. }) =>
+            Err(ActionError::DoorIsLocked(position)),
+        (position, TileType::Door { closed: true, lock: None, .. }) =>
+            Err(ActionError::NoLock(position)),
+        (_, TileType::Door { closed: true, lock: Some(lock), .. }) =>
+            if creature.borrow().inventory().has_key(lock) {
+                Ok(())
+            } else {
+                Err(ActionError::NoKey(lock))
+            },
+        (position, _) => Err(ActionError::NoClosedDoor(position)),
+    }
+}
+
+/// Locks the closed door with the key for its lock
+pub(super) fn lock(
+    world: &mut World,
+    creature: &Weak<CreatureRef>,
+    direction: Direction) -> Result {
+    is_lock_valid(world, creature, direction)?;
+    let (position, tile_type) = adjacent_tile(world, &creature.upgrade().unwrap(), direction)?;
+    if let TileType::Door { lock, .. } = tile_type {
+        set_door(world, position, TileType::Door { closed: true, locked: true, lock });
+    }
+    Ok(())
+}
+
+pub(super) fn is_bash_valid(
+    world: &World,
+    creature: &Weak<CreatureRef>,
+    direction: Direction) -> Result {
+    let creature = creature.upgrade().ok_or(ActionError::SubjectIsDead)?;
+    match adjacent_tile(world, &creature, direction)? {
+        (_, TileType::Door { closed: true, .. }) => Ok(()),
+        (position, _) => Err(ActionError::NoClosedDoor(position)),
+    }
+}
+
+/// Weak creatures fail to break the door, but spend their time anyway.
+/// Broken door loses its lock
+pub(super) fn bash(
+    world: &mut World,
+    creature: &Weak<CreatureRef>,
+    direction: Direction) -> Result {
+    is_bash_valid(world, creature, direction)?;
+    let creature = creature.upgrade().unwrap();
+    let (position, _) = adjacent_tile(world, &creature, direction)?;
+    let roll = world.combat_rng().range(1, 7) as u32;
+    if creature.borrow().attack() + roll <= BASH_DIFFICULTY {
+        return Err(ActionError::BashFailed(position))
+    }
+    set_door(world, position, TileType::Door { closed: false, locked: false, lock: None });
+    Ok(())
+}
+
+pub(super) fn door_cost(creature: &Weak<CreatureRef>) -> u32 {
+    match creature.upgrade() {
+        Some(_) => DOOR_COST,
+        None => 0,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use map::tiles::Tile;
+    use creatures::{Creature, Profession};
+
+    fn setup(lock: Option<u32>) -> (World, Weak<CreatureRef>, Position) {
+        let world = World::new();
+        let character = world.main_character();
+        let position = character.upgrade().unwrap().borrow().position();
+        let door = (position + Direction::Right).unwrap();
+        world.get_level(0).borrow_mut().0[door.x][door.y] =
+            Tile::new(TileType::Door { closed: true, locked: lock.is_some(), lock });
+        (world, character, door)
+    }
+
+    #[test]
+    fn open_and_close() {
+        let (mut world, character, door) = setup(None);
+        open(&mut world, &character, Direction::Right).unwrap();
+        assert!(world.get_level(0).borrow().get_by_pos(door).is_passable());
+        close(&mut world, &character, Direction::Right).unwrap();
+        assert!(!world.get_level(0).borrow().get_by_pos(door).is_passable());
+    }
+
+    #[test]
+    fn move_opens_door() {
+        let (mut world, character, door) = setup(None);
+        let position = character.upgrade().unwrap().borrow().position();
+        Action::Move(character.clone(), Direction::Right).apply(&mut world).unwrap();
+        assert!(world.get_level(0).borrow().get_by_pos(door).is_passable());
+        assert_eq!(character.upgrade().unwrap().borrow().position(), position);
+    }
+
+    #[test]
+    fn locked_door() {
+        let (mut world, character, door) = setup(Some(1));
+        match is_open_valid(&world, &character, Direction::Right) {
+            Err(ActionError::DoorIsLocked(_)) => (),
+            _ => panic!("Locked door can't be opened"),
+        }
+        for _ in 0..20 {
+            match bash(&mut world, &character, Direction::Right) {
+                Err(ActionError::BashFailed(_)) => (),
+                _ => panic!("Demon is too weak to break the door"),
+            }
+        }
+
+        let position = character.upgrade().unwrap().borrow().position();
+        let guard = Rc::new(RefCell::new(Creature::human(
+            String::from("Guard"), 10, 0, position, Profession::Guard)));
+        let broken = (0..50)
+            .any(|_| bash(&mut world, &Rc::downgrade(&guard), Direction::Right).is_ok());
+        assert!(broken, "Strong guard must break the door eventually");
+        assert!(world.get_level(0).borrow().get_by_pos(door).is_passable());
+        assert!(matches!(world.get_level(0).borrow().get_by_pos(door).tile_type,
+                         TileType::Door { lock: None, .. }));
+    }
+
+    #[test]
+    fn locking_with_key() {
+        let (mut world, character, door) = setup(Some(1));
+        let tile_type =
+            |world: &World| world.get_level(0).borrow().get_by_pos(door).tile_type.clone();
+        character.upgrade().unwrap().borrow_mut().inventory_mut()
+            .add(::items::Item::key(1)).unwrap();
+        open(&mut world, &character, Direction::Right).unwrap();
+        close(&mut world, &character, Direction::Right).unwrap();
+        assert!(matches!(tile_type(&world),
+                         TileType::Door { closed: true, locked: false, lock: Some(1) }));
+
+        lock(&mut world, &character, Direction::Right).unwrap();
+        assert!(matches!(tile_type(&world), TileType::Door { locked: true, lock: Some(1), .. }));
+        match is_lock_valid(&world, &character, Direction::Right) {
+            Err(ActionError::DoorIsLocked(_)) => (),
+            _ => panic!("Door is already locked"),
+        }
+
+        character.upgrade().unwrap().borrow_mut().inventory_mut().take(0, 1).unwrap();
+        match is_open_valid(&world, &character, Direction::Right) {
+            Err(ActionError::DoorIsLocked(_)) => (),
+            _ => panic!("Key is gone, door stays locked"),
+        }
+        world.get_level(0).borrow_mut().0[door.x][door.y].tile_type =
+            TileType::Door { closed: true, locked: false, lock: Some(1) };
+        match is_lock_valid(&world, &character, Direction::Right) {
+            Err(ActionError::NoKey(1)) => (),
+            _ => panic!("Door can't be locked without the key"),
+        }
+    }
+
+    #[test]
+    fn door_without_lock() {
+        let (world, character, _) = setup(None);
+        match is_lock_valid(&world, &character, Direction::Right) {
+            Err(ActionError::NoLock(_)) => (),
+            _ => panic!("Door has no lock"),
+        }
+    }
+
+    #[test]
+    fn key_opens_locked_door() {
+        let (mut world, character, door) = setup(Some(1));
+        character.upgrade().unwrap().borrow_mut().inventory_mut()
+            .add(::items::Item::key(1)).unwrap();
+        Action::Move(character.clone(), Direction::Right).apply(&mut world).unwrap();
+        assert!(world.get_level(0).borrow().get_by_pos(door).is_passable());
+    }
+}
@@ -0,0 +1,166 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::rc::Weak;
+
+use world::*;
+use items::{ItemType, Slot};
+use super::*;
+
+const EQUIP_COST: u32 = 100;
+
+pub(super) fn is_equip_valid(creature: &Weak<CreatureRef>, index: usize) -> Result {
+    let creature = creature.upgrade().ok_or(ActionError::SubjectIsDead)?;
+    let creature = creature.borrow();
+    let item = creature.inventory().items().get(index).ok_or(ActionError::NoItem(index))?;
+    match item.item_type {
+        ItemType::Wearable(ref wearable) => {
+            if !creature.can_wear(wearable) {
+                return Err(ActionError::CannotWear(index))
+            }
+            if creature.equipment().contains_key(&wearable.slot) {
+                return Err(ActionError::SlotIsOccupied(wearable.slot))
+            }
+            Ok(())
+        },
+        _ => Err(ActionError::NotWearable(index)),
+    }
+}
+
+pub(super) fn equip(creature: &Weak<CreatureRef>, index: usize) -> Result {
+    is_equip_valid(creature, index)?;
+    let creature = creature.upgrade().unwrap();
+    let mut creature = creature.borrow_mut();
+    let item = creature.inventory_mut().take(index, 1).unwrap();
+    creature.equip(item).expect("Slot must be checked before equipping");
+    Ok(())
+}
+
+pub(super) fn is_unequip_valid(creature: &Weak<CreatureRef>, slot: Slot) -> Result {
+    let creature = creature.upgrade().ok_or(ActionError::SubjectIsDead)?;
+    let creature = creature.borrow();
+    let item = creature.equipment().get(&slot).ok_or(ActionError::SlotIsEmpty(slot))?;
+    let inventory = creature.inventory();
+    if !inventory.can_carry(item) {
+        return Err(ActionError::TooHeavy {
+            weight: item.total_weight(),
+            free: inventory.capacity().saturating_sub(inventory.weight()),
+        })
+    }
+    Ok(())
+}
+
+pub(super) fn unequip(creature: &Weak<CreatureRef>, slot: Slot) -> Result {
+    is_unequip_valid(creature, slot)?;
+    let creature = creature.upgrade().unwrap();
+    let mut creature = creature.borrow_mut();
+    let item = creature.unequip(slot).unwrap();
+    creature.inventory_mut().add(item).expect("Weight must be checked before unequipping");
+    Ok(())
+}
+
+pub(super) fn equip_cost(creature: &Weak<CreatureRef>) -> u32 {
+    match creature.upgrade() {
+        Some(_) => EQUIP_COST,
+        None => 0,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use items::{Item, Modifiers, Wearable};
+
+    fn setup(item: Item) -> (World, Weak<CreatureRef>) {
+        let world = World::new();
+        let character = world.main_character();
+        character.upgrade().unwrap().borrow_mut().inventory_mut().add(item).unwrap();
+        (world, character)
+    }
+
+    fn sword() -> Item {
+        let modifiers = Modifiers { attack: 3, health: 5, ..Modifiers::default() };
+        Item::wearable(String::from("Sword"), 5, Wearable::new(Slot::Weapon, modifiers))
+    }
+
+    #[test]
+    fn equipment_changes_stats() {
+        let (_world, character) = setup(sword());
+        let creature = character.upgrade().unwrap();
+        let (attack, health) = (creature.borrow().attack(), creature.borrow().health().max());
+
+        equip(&character, 0).unwrap();
+        assert_eq!(creature.borrow().attack(), attack + 3);
+        assert_eq!(creature.borrow().health().max(), health + 5);
+        assert!(creature.borrow().inventory().items().is_empty());
+
+        creature.borrow_mut().inventory_mut().add(sword()).unwrap();
+        match is_equip_valid(&character, 0) {
+            Err(ActionError::SlotIsOccupied(Slot::Weapon)) => (),
+            _ => panic!("Only one weapon can be equipped"),
+        }
+
+        unequip(&character, Slot::Weapon).unwrap();
+        assert_eq!(creature.borrow().attack(), attack);
+        assert_eq!(creature.borrow().health().max(), health);
+        match is_unequip_valid(&character, Slot::Weapon) {
+            Err(ActionError::SlotIsEmpty(Slot::Weapon)) => (),
+            _ => panic!("Weapon is already taken off"),
+        }
+    }
+
+    #[test]
+    fn reequipping_doesnt_heal() {
+        let (_world, character) = setup(sword());
+        let creature = character.upgrade().unwrap();
+        creature.borrow_mut().take_damage(10);
+        let health = creature.borrow().health().current();
+
+        equip(&character, 0).unwrap();
+        assert_eq!(creature.borrow().health().current(), health);
+        unequip(&character, Slot::Weapon).unwrap();
+        assert_eq!(creature.borrow().health().current(), health);
+    }
+
+    #[test]
+    fn demon_cant_wear_relic() {
+        let relic = Wearable::new(Slot::Talisman, Modifiers::default()).holy(0);
+        let (_world, character) = setup(Item::wearable(String::from("Relic"), 1, relic));
+        character.upgrade().unwrap().borrow_mut().change_demonicity(1);
+        match is_equip_valid(&character, 0) {
+            Err(ActionError::CannotWear(0)) => (),
+            _ => panic!("Relic burns corrupted demon"),
+        }
+    }
+
+    #[test]
+    fn heavy_armor_slows_down() {
+        let modifiers = Modifiers { defense: 4, speed: -50, ..Modifiers::default() };
+        let armor = Item::wearable(String::from("Plate"), 20, Wearable::new(Slot::Armor, modifiers));
+        let (_world, character) = setup(armor);
+        let cost = Action::Move(character.clone(), Direction::Up).cost();
+        equip(&character, 0).unwrap();
+        assert_eq!(Action::Move(character.clone(), Direction::Up).cost(), cost * 2);
+    }
+}
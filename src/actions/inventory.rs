@@ -0,0 +1,199 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::rc::{Rc, Weak};
+
+use world::*;
+use items::ItemType;
+use events::Event;
+use super::*;
+
+const PICK_UP_COST: u32 = 100;
+const DROP_COST: u32 = 50;
+const USE_COST: u32 = 100;
+
+pub(super) fn is_pick_up_valid(
+    world: &World,
+    creature: &Weak<CreatureRef>,
+    index: usize) -> Result {
+    let creature = creature.upgrade().ok_or(ActionError::SubjectIsDead)?;
+    let creature = creature.borrow();
+    let map = world.get_level(creature.position().level).borrow();
+    let item = map.get_by_pos(creature.position()).items.get(index)
+        .ok_or(ActionError::NoItem(index))?;
+    let inventory = creature.inventory();
+    if !inventory.can_carry(item) {
+        return Err(ActionError::TooHeavy {
+            weight: item.total_weight(),
+            free: inventory.capacity().saturating_sub(inventory.weight()),
+        })
+    }
+    Ok(())
+}
+
+pub(super) fn pick_up(
+    world: &mut World,
+    creature: &Weak<CreatureRef>,
+    index: usize) -> Result {
+    is_pick_up_valid(world, creature, index)?;
+    let creature = creature.upgrade().unwrap();
+    let position = creature.borrow().position();
+    let item = world.get_level(position.level).borrow_mut()
+        .0[position.x][position.y].items.remove(index);
+    creature.borrow_mut().inventory_mut().add(item)
+        .expect("Weight must be checked before picking up");
+    Ok(())
+}
+
+pub(super) fn is_drop_valid(creature: &Weak<CreatureRef>, index: usize) -> Result {
+    let creature = creature.upgrade().ok_or(ActionError::SubjectIsDead)?;
+    let creature = creature.borrow();
+    match creature.inventory().items().get(index) {
+        Some(_) => Ok(()),
+        None => Err(ActionError::NoItem(index)),
+    }
+}
+
+/// Drops the whole stack on the tile creature stands on
+pub(super) fn drop_item(
+    world: &mut World,
+    creature: &Weak<CreatureRef>,
+    index: usize) -> Result {
+    is_drop_valid(creature, index)?;
+    let creature = creature.upgrade().unwrap();
+    let position = creature.borrow().position();
+    let item = creature.borrow_mut().inventory_mut().take(index, u32::MAX).unwrap();
+    world.get_level(position.level).borrow_mut()
+        .0[position.x][position.y].items.push(item);
+    Ok(())
+}
+
+pub(super) fn is_use_valid(creature: &Weak<CreatureRef>, index: usize) -> Result {
+    let creature = creature.upgrade().ok_or(ActionError::SubjectIsDead)?;
+    let creature = creature.borrow();
+    match creature.inventory().items().get(index) {
+        Some(item) => match item.item_type {
+            ItemType::HealingPotion(_) => Ok(()),
+            ItemType::Key(_) | ItemType::Wearable(_) | ItemType::Junk =>
+                Err(ActionError::CannotUse(index)),
+        },
+        None => Err(ActionError::NoItem(index)),
+    }
+}
+
+/// Consumes one item from the stack applying its effect
+pub(super) fn use_item(
+    world: &mut World,
+    creature: &Weak<CreatureRef>,
+    index: usize) -> Result {
+    is_use_valid(creature, index)?;
+    let creature = creature.upgrade().unwrap();
+    let item = creature.borrow_mut().inventory_mut().take(index, 1).unwrap();
+    if let ItemType::HealingPotion(amount) = item.item_type {
+        let amount = creature.borrow_mut().heal(amount);
+        world.log_event(Event::Healed { creature: Rc::downgrade(&creature), amount });
+    }
+    Ok(())
+}
+
+pub(super) fn pick_up_cost(creature: &Weak<CreatureRef>) -> u32 {
+    match creature.upgrade() {
+        Some(_) => PICK_UP_COST,
+        None => 0,
+    }
+}
+
+pub(super) fn drop_cost(creature: &Weak<CreatureRef>) -> u32 {
+    match creature.upgrade() {
+        Some(_) => DROP_COST,
+        None => 0,
+    }
+}
+
+pub(super) fn use_cost(creature: &Weak<CreatureRef>) -> u32 {
+    match creature.upgrade() {
+        Some(_) => USE_COST,
+        None => 0,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use items::Item;
+
+    fn setup() -> (World, Weak<CreatureRef>) {
+        let world = World::new();
+        let character = world.main_character();
+        (world, character)
+    }
+
+    fn put_item(world: &World, character: &Weak<CreatureRef>, item: Item) {
+        let position = character.upgrade().unwrap().borrow().position();
+        world.get_level(0).borrow_mut().0[position.x][position.y].items.push(item);
+    }
+
+    #[test]
+    fn pick_up_and_drop() {
+        let (mut world, character) = setup();
+        put_item(&world, &character, Item::key(1));
+        pick_up(&mut world, &character, 0).unwrap();
+        assert!(character.upgrade().unwrap().borrow().inventory().has_key(1));
+        match is_pick_up_valid(&world, &character, 0) {
+            Err(ActionError::NoItem(0)) => (),
+            _ => panic!("Tile must be empty after picking up"),
+        }
+
+        drop_item(&mut world, &character, 0).unwrap();
+        assert!(character.upgrade().unwrap().borrow().inventory().items().is_empty());
+        is_pick_up_valid(&world, &character, 0).unwrap();
+    }
+
+    #[test]
+    fn too_heavy_item() {
+        let (world, character) = setup();
+        put_item(&world, &character, Item::new(String::from("Anvil"), ItemType::Junk, 1000));
+        match is_pick_up_valid(&world, &character, 0) {
+            Err(ActionError::TooHeavy { weight: 1000, .. }) => (),
+            _ => panic!("Anvil is too heavy to carry"),
+        }
+    }
+
+    #[test]
+    fn drinking_potion() {
+        let (mut world, character) = setup();
+        let creature = character.upgrade().unwrap();
+        creature.borrow_mut().take_damage(5);
+        creature.borrow_mut().inventory_mut().add(Item::healing_potion(3).with_count(2)).unwrap();
+        use_item(&mut world, &character, 0).unwrap();
+        assert_eq!(creature.borrow().health().current(), creature.borrow().health().max() - 2);
+        assert_eq!(creature.borrow().inventory().items()[0].count, 1);
+
+        creature.borrow_mut().inventory_mut().add(Item::key(1)).unwrap();
+        match is_use_valid(&character, 1) {
+            Err(ActionError::CannotUse(1)) => (),
+            _ => panic!("Key can't be used by itself"),
+        }
+    }
+}
@@ -22,6 +22,12 @@
     SOFTWARE.
     */
 mod moving;
+mod combat;
+mod casting;
+mod stairs;
+mod doors;
+mod inventory;
+mod equipment;
 
 use std;
 use std::rc::Weak;
@@ -29,6 +35,9 @@ use std::rc::Weak;
 use World;
 use utils::*;
 use world::*;
+use map::tiles::{Map, LockId};
+use spells::{Spell, SpellTarget};
+use items::Slot;
 
 pub type Result = std::result::Result<(), ActionError>;
 
@@ -41,16 +50,96 @@ pub enum ActionError {
     /// Out of bounds (of [`Map`]), if position is None - it's not exist at all (for example it is negative
     /// and can't be represented by usize)
     OutOfBounds { position: Option<Position>, width: usize, height: usize},
+    /// There is no level with such index, e.g. it hasn't been generated yet
+    NoSuchLevel(usize),
     /// [`Tile`] is occupied by some [`Creature`]
     TileIsOccupied(Weak<CreatureRef>),
     /// [`Tile`] is impassable by it's nature
     TileIsImpassable(Position),
+    /// Diagonal move squeezes past the wall corner while it is forbidden
+    CutsCorner(Position),
+    /// Action was submitted as player's one, but its actor is not the main character
+    NotMainCharacter,
+    /// Actor already has an action waiting in the scheduler
+    ActionAlreadyAssigned,
+    /// There is no living [`Creature`] to act upon at the position
+    NoTarget(Position),
+    /// Caster doesn't have enough mana for the [`Spell`]
+    NotEnoughMana { required: u32, available: u32 },
+    /// Target is farther from the actor than allowed
+    TargetOutOfRange { target: Position, range: usize },
+    /// Target doesn't suit the spell's targeting mode or is already dead
+    InvalidTarget,
+    /// Door can't be opened without the key
+    DoorIsLocked(Position),
+    /// There is no closed door to open or bash at the position
+    NoClosedDoor(Position),
+    /// There is no open door to close at the position
+    NoOpenDoor(Position),
+    /// Door at the position has no lock
+    NoLock(Position),
+    /// Creature doesn't have the key for the lock
+    NoKey(LockId),
+    /// Creature tried to break the door, but it held
+    BashFailed(Position),
+    /// There is no item with such index on the tile or in the inventory
+    NoItem(usize),
+    /// Item doesn't fit into the inventory
+    TooHeavy { weight: u32, free: u32 },
+    /// Item can't be used by itself
+    CannotUse(usize),
+    /// Item with given index can't be equipped at all
+    NotWearable(usize),
+    /// Item with given index is unsuitable for the creature
+    CannotWear(usize),
+    /// Something is already equipped in the slot
+    SlotIsOccupied(Slot),
+    /// Nothing is equipped in the slot
+    SlotIsEmpty(Slot),
+    /// There are no stairs leading in the needed direction under the creature
+    NoStairs,
+    /// Level with such index couldn't be generated with all its stairs
+    /// reachable, so the stairs lead nowhere for now
+    GenerationFailed(usize),
+    /// Caster is not corrupted enough for the spell (or not a demon at all)
+    SpellIsLocked,
 }
 
+/// Time-points that creature spends waiting for one turn
+const WAIT_COST: u32 = 100;
+
 pub enum Action {
     Move(Weak<CreatureRef>, Direction),
+    /// Melee attack of the creature standing in given direction
+    Attack(Weak<CreatureRef>, Direction),
+    /// Cast the spell, spending caster's mana
+    Cast(Weak<CreatureRef>, Spell, SpellTarget),
+    /// Open the closed door, locked one needs the key in the inventory
+    Open(Weak<CreatureRef>, Direction),
+    Close(Weak<CreatureRef>, Direction),
+    /// Lock the closed door, needs the key for its lock
+    Lock(Weak<CreatureRef>, Direction),
+    /// Try to break the closed (possibly locked) door
+    Bash(Weak<CreatureRef>, Direction),
+    /// Pick up the item with given index from the tile creature stands on
+    PickUp(Weak<CreatureRef>, usize),
+    /// Drop the item with given index from the inventory
+    Drop(Weak<CreatureRef>, usize),
+    /// Use (and usually consume) the item with given index in the inventory
+    Use(Weak<CreatureRef>, usize),
+    /// Equip the item with given index from the inventory
+    Equip(Weak<CreatureRef>, usize),
+    /// Take off the item from the slot back into the inventory
+    Unequip(Weak<CreatureRef>, Slot),
+    /// Go up the stairs creature stands on
+    Ascend(Weak<CreatureRef>),
+    /// Go down the stairs creature stands on
+    Descend(Weak<CreatureRef>),
+    /// Skip turn doing nothing
+    Wait(Weak<CreatureRef>),
+    /// Does nothing, but costs exactly the given time
     #[cfg(test)]
-    MockAction(Weak<CreatureRef>, u32),
+    Mock(Weak<CreatureRef>, i32),
 }
 
 /// Abstracts the action to be commited in the [`World`]
@@ -59,36 +148,138 @@ impl Action {
         match *self {
             Action::Move(ref creature, direction) =>
                 moving::move_creature(world, creature, direction),
-           #[cfg(test)]
-            Action::MockAction(_, _) => Ok(()),
+            Action::Attack(ref creature, direction) =>
+                combat::attack(world, creature, direction),
+            Action::Cast(ref creature, ref spell, ref target) =>
+                casting::cast(world, creature, spell, target),
+            Action::Open(ref creature, direction) => doors::open(world, creature, direction),
+            Action::Close(ref creature, direction) => doors::close(world, creature, direction),
+            Action::Lock(ref creature, direction) => doors::lock(world, creature, direction),
+            Action::Bash(ref creature, direction) => doors::bash(world, creature, direction),
+            Action::PickUp(ref creature, index) => inventory::pick_up(world, creature, index),
+            Action::Drop(ref creature, index) => inventory::drop_item(world, creature, index),
+            Action::Use(ref creature, index) => inventory::use_item(world, creature, index),
+            Action::Equip(ref creature, index) => equipment::equip(creature, index),
+            Action::Unequip(ref creature, slot) => equipment::unequip(creature, slot),
+            Action::Ascend(ref creature) => stairs::use_stairs(world, creature, false),
+            Action::Descend(ref creature) => stairs::use_stairs(world, creature, true),
+            Action::Wait(_) => Ok(()),
+            #[cfg(test)]
+            Action::Mock(_, _) => Ok(()),
+        }
+    }
+
+    /// Cost of action in time-points, used for scheduling. Movement depends
+    /// on actor's speed, everything else on its action speed. Zero cost
+    /// action is free, negative one refunds time to the following actions
+    pub(crate) fn cost(&self) -> i32 {
+        let cost = self.base_cost();
+        match (self, self.actor().upgrade()) {
+            #[cfg(test)]
+            (&Action::Mock(_, cost), _) => cost,
+            (&Action::Move(..), Some(actor)) => actor.borrow().move_cost(cost) as i32,
+            (_, Some(actor)) => actor.borrow().action_cost(cost) as i32,
+            (_, None) => cost as i32,
         }
     }
 
-    /// Cost of action in time-points, used for scheduling (
-    pub(crate) fn cost(&self) -> u32 {
+    fn base_cost(&self) -> u32 {
         match *self {
             Action::Move(ref creature, direction) =>
                 moving::move_cost(creature, direction),
+            Action::Attack(ref creature, _) => combat::attack_cost(creature),
+            Action::Cast(ref creature, ref spell, _) => casting::cast_cost(creature, spell),
+            Action::Open(ref creature, _) |
+            Action::Close(ref creature, _) |
+            Action::Lock(ref creature, _) |
+            Action::Bash(ref creature, _) => doors::door_cost(creature),
+            Action::PickUp(ref creature, _) => inventory::pick_up_cost(creature),
+            Action::Drop(ref creature, _) => inventory::drop_cost(creature),
+            Action::Use(ref creature, _) => inventory::use_cost(creature),
+            Action::Equip(ref creature, _) | Action::Unequip(ref creature, _) =>
+                equipment::equip_cost(creature),
+            Action::Ascend(ref creature) | Action::Descend(ref creature) =>
+                stairs::stairs_cost(creature),
+            Action::Wait(_) => WAIT_COST,
             #[cfg(test)]
-            Action::MockAction(_, cost) => cost,
+            Action::Mock(_, cost) => std::cmp::max(cost, 0) as u32,
         }
     }
 
-    pub(crate) fn is_valid(&self, world: &World) -> Result {
+    pub fn is_valid(&self, world: &World) -> Result {
+        match self.actor().upgrade() {
+            Some(ref actor) if actor.borrow().is_alive() => (),
+            _ => return Err(ActionError::SubjectIsDead),
+        }
         match *self {
             Action::Move(ref creature, direction) =>
                 moving::is_move_valid(world, creature, direction),
+            Action::Attack(ref creature, direction) =>
+                combat::is_attack_valid(world, creature, direction),
+            Action::Cast(ref creature, ref spell, ref target) =>
+                casting::is_cast_valid(world, creature, spell, target),
+            Action::Open(ref creature, direction) =>
+                doors::is_open_valid(world, creature, direction),
+            Action::Close(ref creature, direction) =>
+                doors::is_close_valid(world, creature, direction),
+            Action::Lock(ref creature, direction) =>
+                doors::is_lock_valid(world, creature, direction),
+            Action::Bash(ref creature, direction) =>
+                doors::is_bash_valid(world, creature, direction),
+            Action::PickUp(ref creature, index) =>
+                inventory::is_pick_up_valid(world, creature, index),
+            Action::Drop(ref creature, index) => inventory::is_drop_valid(creature, index),
+            Action::Use(ref creature, index) => inventory::is_use_valid(creature, index),
+            Action::Equip(ref creature, index) => equipment::is_equip_valid(creature, index),
+            Action::Unequip(ref creature, slot) => equipment::is_unequip_valid(creature, slot),
+            Action::Ascend(ref creature) => stairs::is_stairs_valid(world, creature, false),
+            Action::Descend(ref creature) => stairs::is_stairs_valid(world, creature, true),
+            Action::Wait(_) => Ok(()),
             #[cfg(test)]
-            Action::MockAction(_, _) => Ok(()),
-
+            Action::Mock(_, _) => Ok(()),
         }
     }
 
     pub fn actor(&self) -> &Weak<CreatureRef> {
         match *self {
             Action::Move(ref creature, _) => creature,
+            Action::Attack(ref creature, _) => creature,
+            Action::Cast(ref creature, _, _) => creature,
+            Action::Open(ref creature, _) => creature,
+            Action::Close(ref creature, _) => creature,
+            Action::Lock(ref creature, _) => creature,
+            Action::Bash(ref creature, _) => creature,
+            Action::PickUp(ref creature, _) => creature,
+            Action::Drop(ref creature, _) => creature,
+            Action::Use(ref creature, _) => creature,
+            Action::Equip(ref creature, _) => creature,
+            Action::Unequip(ref creature, _) => creature,
+            Action::Ascend(ref creature) => creature,
+            Action::Descend(ref creature) => creature,
+            Action::Wait(ref creature) => creature,
             #[cfg(test)]
-            Action::MockAction(ref creature, _) => creature,
+            Action::Mock(ref creature, _) => creature,
         }
     }
 }
+
+/// Returns position next to the given one if it is inside the map
+fn adjacent_position(map: &Map, position: Position, direction: Direction)
+                     -> std::result::Result<Position, ActionError> {
+    let Map(ref tiles) = *map;
+    let new_pos = (position + direction)
+        .ok_or(ActionError::OutOfBounds {
+            position: None,
+            width: tiles.len(),
+            height: tiles[0].len(),
+        })?;
+
+    if tiles.len() <= new_pos.x || tiles[0].len() <= new_pos.y {
+        return Err(ActionError::OutOfBounds {
+            position: Some(new_pos),
+            width: tiles.len(),
+            height: tiles[0].len(),
+        })
+    }
+    Ok(new_pos)
+}
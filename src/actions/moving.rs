@@ -21,39 +21,37 @@
     OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
     SOFTWARE.
     */
-use std::rc::Weak;
+use std::rc::{Rc, Weak};
 
 use utils::*;
 use world::*;
-use map::tiles::*;
+use map::tiles::TileType;
 use super::*;
 
 pub(super) fn is_move_valid(
     world: &World,
     creature: &Weak<CreatureRef>,
     direction: Direction) -> Result {
-    if let Some(creature) = creature.upgrade() {
-        let creature = creature.borrow();
-        let Map(ref map) = *world.get_level(creature.position().level).borrow_mut();
-        let new_pos = (creature.position() + direction)
-            .ok_or(ActionError::OutOfBounds {
-                position: None,
-                width: map.len(),
-                height: map[0].len(),
-            })?;
-
-        if map.len() <= new_pos.x || map[0].len() <= new_pos.y {
-            return Err(ActionError::OutOfBounds {
-                position: Some(new_pos),
-                width: map.len(),
-                height: map[0].len(),
-            })
+    if let Some(creature_rc) = creature.upgrade() {
+        if combat::hostile_target(world, &creature_rc, direction).is_some() {
+            return combat::is_attack_valid(world, creature, direction)
         }
+        let creature = creature_rc.borrow();
+        let map = world.get_level(creature.position().level).borrow();
+        let new_pos = adjacent_position(&map, creature.position(), direction)?;
 
-        let ref tile = map[new_pos.x][new_pos.y];
+        if !world.corner_cutting() && map.cuts_corner(creature.position(), direction) {
+            return Err(ActionError::CutsCorner(new_pos))
+        }
+
+        let tile = map.get_by_pos(new_pos);
         if let Some(ref creature) = tile.creature {
             return Err(ActionError::TileIsOccupied(creature.clone())) // weak ref
         }
+        if let TileType::Door { closed: true, .. } = tile.tile_type {
+            drop(map);
+            return doors::is_open_valid(world, &Rc::downgrade(&creature_rc), direction)
+        }
         if !tile.is_passable() {
             return Err(ActionError::TileIsImpassable(new_pos))
         }
@@ -64,7 +62,8 @@ pub(super) fn is_move_valid(
     }
 }
 
-
+/// Moves creature, attacks hostile creature standing in the way or opens
+/// the closed door
 pub(super) fn move_creature(
     world: &mut World,
     creature: &Weak<CreatureRef>,
@@ -74,20 +73,25 @@ pub(super) fn move_creature(
     //       is_valid_function
     let creature_ref = creature;
     is_move_valid(world, creature, direction)?;
-    let mut creature = creature.upgrade().unwrap();
-    let mut creature = creature.borrow_mut();
-    let Map(ref mut map) = *world.get_level(creature.position().level).borrow_mut();
-    let new_pos = (creature.position() + direction).unwrap();
-    map[new_pos.x][new_pos.y].creature = Some(creature_ref.clone());
-    map[creature.position().x][creature.position().y].creature = None;
-    creature.set_position(new_pos);
+    let creature = creature.upgrade().unwrap();
+    if combat::hostile_target(world, &creature, direction).is_some() {
+        return combat::attack(world, creature_ref, direction)
+    }
+    let new_pos = (creature.borrow().position() + direction).unwrap();
+    let is_closed_door = matches!(
+        world.get_level(new_pos.level).borrow().get_by_pos(new_pos).tile_type,
+        TileType::Door { closed: true, .. });
+    if is_closed_door {
+        return doors::open(world, creature_ref, direction)
+    }
+    world.relocate_creature(&creature, new_pos);
     Ok(())
 }
 
 
 pub(super) fn move_cost(creature: &Weak<CreatureRef>, direction: Direction) -> u32 {
     match creature.upgrade() {
-        Some(_) => 100, // TODO: replace hardcode with more creature-specific calculation
+        Some(_) => direction.step_cost(),
         None => 0,
     }
 }
@@ -96,21 +100,46 @@ pub(super) fn move_cost(creature: &Weak<CreatureRef>, direction: Direction) -> u
 #[cfg(test)]
 mod tests {
     use super::*;
+    use map::tiles::*;
 
     fn setup() -> (World, Weak<CreatureRef>, Position) {
-        let mut world = World::new();
+        let world = World::new();
         let character = world.main_character();
-        let mut position = match character.upgrade() {
+        let position = match character.upgrade() {
             Some(ref character) => character.borrow().position(),
             None => panic!("No main character!")
         };
         (world, character, position)
     }
 
+    #[test]
+    fn diagonal_move_costs_more() {
+        let (_world, character, _) = setup();
+        assert!(move_cost(&character, Direction::UpLeft) > move_cost(&character, Direction::Up));
+    }
+
+    #[test]
+    fn corner_cutting_rule() {
+        let (mut world, character, _) = setup();
+        let corner = Position { level: 0, x: 1, y: 1 };
+        world.relocate_creature(&character.upgrade().unwrap(), corner);
+        {
+            let Map(ref mut tiles) = *world.get_level(0).borrow_mut();
+            tiles[2][1] = Tile::new(TileType::Wall);
+        }
+        assert!(is_move_valid(&world, &character, Direction::DownRight).is_ok());
+
+        world.set_corner_cutting(false);
+        match is_move_valid(&world, &character, Direction::DownRight) {
+            Err(ActionError::CutsCorner(_)) => (),
+            _ => panic!("Diagonal move past the wall must be forbidden"),
+        }
+    }
+
     #[test]
     fn valid_move() {
         let (mut world, character, pos) = setup();
-        move_creature(&mut world, &character, Direction::Right);
+        move_creature(&mut world, &character, Direction::Right).unwrap();
         let actual = character.upgrade().map(|a| a.borrow().position()).unwrap();
         let expected = (pos + Direction::Right).unwrap();
         assert_eq!(actual, expected);
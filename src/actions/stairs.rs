@@ -0,0 +1,127 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::rc::{Rc, Weak};
+
+use world::*;
+use map::tiles::TileType;
+use events::Event;
+use super::*;
+
+const STAIRS_COST: u32 = 100;
+
+pub(super) fn is_stairs_valid(
+    world: &World,
+    creature: &Weak<CreatureRef>,
+    down: bool) -> Result {
+    let creature = creature.upgrade().ok_or(ActionError::SubjectIsDead)?;
+    let position = creature.borrow().position();
+    match world.get_level(position.level).borrow().get_by_pos(position).tile_type {
+        TileType::Stairs { down: stairs_down } if stairs_down == down => (),
+        _ => return Err(ActionError::NoStairs),
+    }
+    // unlinked stairs down get linked to the free tile when followed,
+    // unlinked stairs up (e.g. from a vault) lead nowhere
+    match world.stairs_destination(position) {
+        Some(destination) => {
+            let map = world.get_level(destination.level).borrow();
+            if let Some(ref other) = map.get_by_pos(destination).creature {
+                return Err(ActionError::TileIsOccupied(other.clone()))
+            }
+        },
+        None if !down => return Err(ActionError::NoStairs),
+        None => (),
+    }
+    Ok(())
+}
+
+pub(super) fn use_stairs(
+    world: &mut World,
+    creature: &Weak<CreatureRef>,
+    down: bool) -> Result {
+    is_stairs_valid(world, creature, down)?;
+    let creature = creature.upgrade().unwrap();
+    let position = creature.borrow().position();
+    let destination = world.follow_stairs(position)?;
+    world.relocate_creature(&creature, destination);
+    world.log_event(Event::LevelChanged {
+        creature: Rc::downgrade(&creature),
+        from: position.level,
+        to: destination.level,
+    });
+    Ok(())
+}
+
+pub(super) fn stairs_cost(creature: &Weak<CreatureRef>) -> u32 {
+    match creature.upgrade() {
+        Some(_) => STAIRS_COST,
+        None => 0,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_stairs_under_creature() {
+        let world = World::new();
+        match is_stairs_valid(&world, &world.main_character(), true) {
+            Err(ActionError::NoStairs) => (),
+            _ => panic!("Main character doesn't stand on the stairs"),
+        }
+    }
+
+    #[test]
+    fn several_stairs_down() {
+        let mut world = World::new();
+        let character = world.main_character();
+        let creature = character.upgrade().unwrap();
+        let start = creature.borrow().position();
+        let other = world.get_level(0).borrow().tiles()
+            .find(|&(_, _, tile)| matches!(tile.tile_type, TileType::Stairs { down: true }))
+            .map(|(x, y, _)| Position { level: 0, x, y })
+            .unwrap();
+        world.get_level(0).borrow_mut().0[start.x][start.y].tile_type =
+            TileType::Stairs { down: true };
+
+        use_stairs(&mut world, &character, true).unwrap();
+        world.relocate_creature(&creature, other);
+        use_stairs(&mut world, &character, true).unwrap();
+        assert_eq!(world.levels_count(), 2);
+        assert_eq!(creature.borrow().position().level, 1);
+    }
+
+    #[test]
+    fn unlinked_stairs_up() {
+        let world = World::new();
+        let position = world.main_character().upgrade().unwrap().borrow().position();
+        world.get_level(0).borrow_mut().0[position.x][position.y].tile_type =
+            TileType::Stairs { down: false };
+        match is_stairs_valid(&world, &world.main_character(), false) {
+            Err(ActionError::NoStairs) => (),
+            _ => panic!("Stairs up on the first level lead nowhere"),
+        }
+    }
+}
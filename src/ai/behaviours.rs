@@ -0,0 +1,245 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::rc::Rc;
+
+use World;
+use actions::Action;
+use world::CreatureRef;
+use demonicity::Reaction;
+use map::fov::FieldOfView;
+use map::pathfinding::Goal;
+use utils::*;
+use super::*;
+
+/// Walks around without any purpose
+#[derive(Debug, Default)]
+pub struct Wander;
+
+/// Goes after the main character once it is seen and attacks it
+#[derive(Debug, Default)]
+pub struct ChasePlayer;
+
+/// Runs away from the main character when hurt or scared, otherwise
+/// behaves as usual
+#[derive(Debug)]
+pub struct FleeAtLowHealth {
+    /// Percent of max health below which creature flees
+    pub threshold: u32,
+    pub otherwise: Rc<dyn Behaviour>,
+}
+
+/// Stays at the post, attacking the main character when it comes close
+#[derive(Debug)]
+pub struct Guard {
+    pub post: Position,
+    pub radius: usize,
+}
+
+impl Wander {
+    pub fn new() -> Wander {
+        Wander
+    }
+}
+
+impl Behaviour for Wander {
+    fn next_action(&self, world: &World, creature: &Rc<CreatureRef>) -> Action {
+        let position = creature.borrow().position();
+        let mut directions = Direction::all().to_vec();
+        world.ai_rng().shuffle(&mut directions);
+        directions.into_iter()
+            .find(|&direction| can_step(world, position, direction))
+            .map(|direction| Action::Move(Rc::downgrade(creature), direction))
+            .unwrap_or_else(|| Action::Wait(Rc::downgrade(creature)))
+    }
+}
+
+/// Attacks main character if it is adjacent or comes closer to it
+fn pursue_player(world: &World, creature: &Rc<CreatureRef>, target: Position) -> Action {
+    let position = creature.borrow().position();
+    let action = adjacent_direction(position, target)
+        .map(|direction| Action::Attack(Rc::downgrade(creature), direction))
+        .or_else(|| {
+            let dijkstra_map = world.dijkstra_map(position.level, Goal::MainCharacter)?;
+            descend(world, position, &dijkstra_map)
+                .map(|direction| Action::Move(Rc::downgrade(creature), direction))
+        });
+    action.unwrap_or_else(|| Action::Wait(Rc::downgrade(creature)))
+}
+
+impl Behaviour for ChasePlayer {
+    fn next_action(&self, world: &World, creature: &Rc<CreatureRef>) -> Action {
+        let (position, sight) = {
+            let creature = creature.borrow();
+            (creature.position(), creature.sight())
+        };
+        let target = hostile_main_character(world, creature)
+            .map(|player| player.borrow().position())
+            .filter(|&target| {
+                let map = world.get_level(position.level).borrow();
+                FieldOfView::new(&map, position, sight).is_visible(target)
+            });
+        match target {
+            Some(target) => pursue_player(world, creature, target),
+            None => Action::Wait(Rc::downgrade(creature)),
+        }
+    }
+}
+
+impl Behaviour for FleeAtLowHealth {
+    fn next_action(&self, world: &World, creature: &Rc<CreatureRef>) -> Action {
+        let player = match hostile_main_character(world, creature) {
+            Some(player) => player,
+            None => return self.otherwise.next_action(world, creature),
+        };
+        let (position, scared) = {
+            let creature = creature.borrow();
+            let health = creature.health();
+            let hurt = health.current() * 100 < health.max() * self.threshold;
+            let terrified = creature.reaction_to(&player.borrow()) == Reaction::Terrified;
+            (creature.position(), hurt || terrified || creature.is_broken())
+        };
+        if !scared {
+            return self.otherwise.next_action(world, creature)
+        }
+        let flee_map = world.dijkstra_map(position.level, Goal::MainCharacter).map(|dijkstra_map| {
+            let map = world.get_level(position.level).borrow();
+            dijkstra_map.inverted(&map)
+        });
+        match flee_map.and_then(|flee_map| descend(world, position, &flee_map)) {
+            Some(direction) => Action::Move(Rc::downgrade(creature), direction),
+            // cornered, nothing to do but fight
+            None => self.otherwise.next_action(world, creature),
+        }
+    }
+}
+
+impl Behaviour for Guard {
+    fn next_action(&self, world: &World, creature: &Rc<CreatureRef>) -> Action {
+        let position = creature.borrow().position();
+        if let Some(player) = hostile_main_character(world, creature) {
+            let target = player.borrow().position();
+            if target.distance(&self.post) <= self.radius {
+                return pursue_player(world, creature, target)
+            }
+        }
+        if position == self.post {
+            return Action::Wait(Rc::downgrade(creature))
+        }
+        step_towards(world, position, self.post)
+            .map(|direction| Action::Move(Rc::downgrade(creature), direction))
+            .unwrap_or_else(|| Action::Wait(Rc::downgrade(creature)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use creatures::{Creature, Profession};
+    use map::tiles::*;
+
+    fn setup(offset: usize) -> (World, Rc<CreatureRef>) {
+        let mut world = World::new();
+        let position = world.main_character().upgrade().unwrap().borrow().position();
+        let guard = Creature::human(String::from("Guard"), 10, 0,
+                                    Position { x: position.x + offset, ..position },
+                                    Profession::Guard);
+        let guard = world.spawn_creature(guard).unwrap().upgrade().unwrap();
+        // humans notice only corrupted demons
+        world.main_character().upgrade().unwrap().borrow_mut().change_demonicity(50);
+        (world, guard)
+    }
+
+    #[test]
+    fn chase_approaches_player() {
+        let (world, guard) = setup(3);
+        match ChasePlayer.next_action(&world, &guard) {
+            Action::Move(_, Direction::Left) => (),
+            _ => panic!("Guard must step towards the player"),
+        }
+    }
+
+    #[test]
+    fn chase_attacks_adjacent_player() {
+        let (world, guard) = setup(1);
+        match ChasePlayer.next_action(&world, &guard) {
+            Action::Attack(_, Direction::Left) => (),
+            _ => panic!("Guard must attack the player"),
+        }
+    }
+
+    #[test]
+    fn chase_needs_to_see_player() {
+        let (world, guard) = setup(3);
+        {
+            let position = guard.borrow().position();
+            let Map(ref mut tiles) = *world.get_level(0).borrow_mut();
+            for tile in tiles[position.x - 1].iter_mut() {
+                *tile = Tile::new(TileType::Wall);
+            }
+        }
+        match ChasePlayer.next_action(&world, &guard) {
+            Action::Wait(_) => (),
+            _ => panic!("Guard can't chase the player behind the wall"),
+        }
+    }
+
+    #[test]
+    fn chase_limited_by_sight() {
+        let (world, guard) = setup(guard_sight() + 1);
+        match ChasePlayer.next_action(&world, &guard) {
+            Action::Wait(_) => (),
+            _ => panic!("Player is too far for the guard to see"),
+        }
+    }
+
+    fn guard_sight() -> usize {
+        Creature::human(String::from("Guard"), 10, 0, Position { level: 0, x: 0, y: 0 },
+                        Profession::Guard).sight()
+    }
+
+    #[test]
+    fn flee_when_hurt() {
+        let (world, guard) = setup(2);
+        guard.borrow_mut().take_damage(8);
+        let behaviour = FleeAtLowHealth { threshold: 50, otherwise: Rc::new(ChasePlayer) };
+        let position = guard.borrow().position();
+        let player = world.main_character().upgrade().unwrap().borrow().position();
+        match behaviour.next_action(&world, &guard) {
+            Action::Move(_, direction) =>
+                assert!((position + direction).unwrap().distance(&player) > position.distance(&player)),
+            _ => panic!("Hurt guard must run away"),
+        }
+    }
+
+    #[test]
+    fn guard_returns_to_post() {
+        let (world, guard) = setup(4);
+        let post = Position { y: guard.borrow().position().y + 3, ..guard.borrow().position() };
+        match (Guard { post, radius: 1 }).next_action(&world, &guard) {
+            Action::Move(_, Direction::Down) => (),
+            _ => panic!("Guard must return to the post"),
+        }
+    }
+}
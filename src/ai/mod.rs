@@ -0,0 +1,87 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+mod behaviours;
+
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use World;
+use actions::Action;
+use world::CreatureRef;
+use utils::*;
+use map::pathfinding::DijkstraMap;
+use status::Status;
+
+pub use self::behaviours::*;
+
+/// Decides what non-player creature does next, stored in [`Creature`] and
+/// asked by [`World::turn`] whenever creature has nothing to do
+pub trait Behaviour: Debug {
+    /// Returned action must have `creature` as its actor
+    fn next_action(&self, world: &World, creature: &Rc<CreatureRef>) -> Action;
+}
+
+/// Creature can step in the direction without attacking anyone
+fn can_step(world: &World, position: Position, direction: Direction) -> bool {
+    let map = world.get_level(position.level).borrow();
+    let width = map.0.len();
+    let height = map.0[0].len();
+    match position + direction {
+        Some(next) if next.x < width && next.y < height => {
+            let tile = map.get_by_pos(next);
+            tile.is_passable() && tile.creature.is_none()
+        },
+        _ => false,
+    }
+}
+
+/// First step of the shortest path to the target going around creatures
+fn step_towards(world: &World, position: Position, target: Position) -> Option<Direction> {
+    world.find_path(position, target, true).and_then(|path| path.first().cloned())
+}
+
+/// Step down the Dijkstra map going around creatures
+fn descend(world: &World, position: Position, dijkstra_map: &DijkstraMap) -> Option<Direction> {
+    let map = world.get_level(position.level).borrow();
+    dijkstra_map.descend(&map, position, true)
+}
+
+/// Direction in which the target stands right next to position
+fn adjacent_direction(position: Position, target: Position) -> Option<Direction> {
+    Direction::all().iter()
+        .cloned()
+        .find(|&direction| position + direction == Some(target))
+}
+
+/// Main character if it is alive, visible and hostile to the creature
+fn hostile_main_character(world: &World, creature: &Rc<CreatureRef>) -> Option<Rc<CreatureRef>> {
+    world.main_character()
+        .upgrade()
+        .filter(|player| !player.identical(creature))
+        .filter(|player| {
+            let player = player.borrow();
+            player.is_alive() && !player.has_status(Status::Invisible) &&
+                creature.borrow().is_hostile_to(&player)
+        })
+}
@@ -22,9 +22,31 @@
     SOFTWARE.
     */
 use std::cmp;
+use std::collections::HashMap;
+use std::rc::Rc;
+
 use utils::*;
+use ai::Behaviour;
+use map::memory::MapMemory;
+use demonicity::*;
+use spells::Spell;
+use status::{Status, StatusEffect};
+use items::{Inventory, Item, ItemType, Modifiers, Slot, Wearable};
 
 const MAX_DEMONICITY: u32 = 100;
+const DEFAULT_ATTACK: u32 = 5;
+const DEFAULT_DEFENSE: u32 = 1;
+const MAX_FAITH: u32 = 100;
+const MAX_MORALE: u32 = 100;
+/// Human with morale below this value is too scared to fight properly
+const BROKEN_MORALE: u32 = 25;
+const DEFAULT_SIGHT: usize = 8;
+/// Speed of the usual creature, action costs are given for it
+const NORMAL_SPEED: u32 = 100;
+/// Nothing can be slowed down more than ten times
+const MIN_SPEED: u32 = 10;
+/// Total weight of items creature can carry
+const DEFAULT_CAPACITY: u32 = 50;
 
 #[derive(Debug)]
 pub struct Points {
@@ -32,42 +54,178 @@ pub struct Points {
     max: u32,
 }
 
+impl Points {
+    pub fn current(&self) -> u32 {
+        self.current
+    }
+
+    pub fn max(&self) -> u32 {
+        self.max
+    }
+
+    /// Decreases current value not going below zero, returns actual decrease
+    pub(crate) fn decrease(&mut self, amount: u32) -> u32 {
+        let decrease = cmp::min(amount, self.current);
+        self.current -= decrease;
+        decrease
+    }
+
+    /// Changes max value, current value stays the same unless it has to be
+    /// clamped, so raising and lowering max back never restores points
+    pub(crate) fn change_max(&mut self, delta: i32) {
+        if delta >= 0 {
+            self.max += delta as u32;
+        } else {
+            self.max = self.max.saturating_sub(delta.unsigned_abs());
+            self.current = cmp::min(self.current, self.max);
+        }
+    }
+
+    /// Increases current value not going above max, returns actual increase
+    pub(crate) fn increase(&mut self, amount: u32) -> u32 {
+        let increase = cmp::min(amount, self.max - self.current);
+        self.current += increase;
+        increase
+    }
+}
+
 #[derive(Debug)]
 pub struct Creature {
     name: String,
     position: Position,
     health: Points,
     mana: Points,
+    attack: u32,
+    defense: u32,
     creature_type: CreatureType,
+    behaviour: Option<Rc<dyn Behaviour>>,
+    /// Radius of the field of view
+    sight: usize,
+    /// Movement speed, actions take `NORMAL_SPEED / speed` of their usual cost
+    speed: u32,
+    /// Speed of all actions but movement
+    action_speed: u32,
+    memory: MapMemory,
+    inventory: Inventory,
+    equipment: HashMap<Slot, Item>,
+    statuses: Vec<StatusEffect>,
 }
 
 #[derive(Debug)]
 pub enum CreatureType {
-    Human,
+    /// Faith protects from demonic harm, morale keeps human fighting
+    Human { faith: Points, morale: Points, profession: Profession },
     Demon { demonicity: Points },
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Profession {
+    Guard,
+    Jailer,
+    Priest,
+    Peasant,
+}
+
+impl Profession {
+    /// Attack and defense of the profession
+    fn combat_stats(self) -> (u32, u32) {
+        match self {
+            Profession::Guard => (7, 3),
+            Profession::Jailer => (6, 2),
+            Profession::Priest => (3, 1),
+            Profession::Peasant => (3, 0),
+        }
+    }
+
+    /// Initial faith and morale of the profession
+    fn spirit(self) -> (u32, u32) {
+        match self {
+            Profession::Guard => (20, 60),
+            Profession::Jailer => (10, 50),
+            Profession::Priest => (80, 40),
+            Profession::Peasant => (30, 20),
+        }
+    }
+}
+
 
 impl Creature {
-    pub(crate) fn demon(name: String,
+    pub fn demon(name: String,
                  health: u32,
                  mana: u32,
                  position: Position,
                  initial_demonicity: u32) -> Creature {
+        let demonicity = cmp::min(initial_demonicity, MAX_DEMONICITY);
+        let corruption = Corruption::from_demonicity(demonicity);
+        let health = health + corruption.health_bonus();
+        let mana = mana + corruption.mana_bonus();
         Creature {
-            name: name,
-            position: position,
+            name,
+            position,
             mana: Points { current: mana, max: mana },
             health: Points { current: health, max: health },
+            attack: DEFAULT_ATTACK,
+            defense: DEFAULT_DEFENSE,
             creature_type: CreatureType::Demon {
                 demonicity: Points {
-                    current: cmp::min(initial_demonicity, MAX_DEMONICITY),
+                    current: demonicity,
                     max: MAX_DEMONICITY
                 },
             },
+            behaviour: None,
+            sight: DEFAULT_SIGHT,
+            speed: NORMAL_SPEED,
+            action_speed: NORMAL_SPEED,
+            memory: MapMemory::new(),
+            inventory: Inventory::new(DEFAULT_CAPACITY),
+            equipment: HashMap::new(),
+            statuses: vec![],
+        }
+    }
+
+    pub fn human(name: String,
+                 health: u32,
+                 mana: u32,
+                 position: Position,
+                 profession: Profession) -> Creature {
+        let (attack, defense) = profession.combat_stats();
+        let (faith, morale) = profession.spirit();
+        Creature {
+            name,
+            position,
+            mana: Points { current: mana, max: mana },
+            health: Points { current: health, max: health },
+            attack,
+            defense,
+            creature_type: CreatureType::Human {
+                faith: Points { current: faith, max: MAX_FAITH },
+                morale: Points { current: morale, max: MAX_MORALE },
+                profession,
+            },
+            behaviour: None,
+            sight: DEFAULT_SIGHT,
+            speed: NORMAL_SPEED,
+            action_speed: NORMAL_SPEED,
+            memory: MapMemory::new(),
+            inventory: Inventory::new(DEFAULT_CAPACITY),
+            equipment: HashMap::new(),
+            statuses: vec![],
         }
     }
 
+    /// Sets behaviour that decides actions of the creature
+    pub fn with_behaviour(mut self, behaviour: Rc<dyn Behaviour>) -> Creature {
+        self.behaviour = Some(behaviour);
+        self
+    }
+
+    /// Sets movement and action speeds, 100 is the usual one
+    pub fn with_speed(mut self, speed: u32, action_speed: u32) -> Creature {
+        self.speed = speed;
+        self.action_speed = action_speed;
+        self
+    }
+
     pub fn position(&self) -> Position {
         self.position
     }
@@ -76,7 +234,381 @@ impl Creature {
         &self.name
     }
 
+    pub fn health(&self) -> &Points {
+        &self.health
+    }
+
+    pub fn mana(&self) -> &Points {
+        &self.mana
+    }
+
+    pub fn creature_type(&self) -> &CreatureType {
+        &self.creature_type
+    }
+
+    pub fn sight(&self) -> usize {
+        self.sight
+    }
+
+    /// Tiles creature has seen so far
+    pub fn memory(&self) -> &MapMemory {
+        &self.memory
+    }
+
+    pub(crate) fn memory_mut(&mut self) -> &mut MapMemory {
+        &mut self.memory
+    }
+
+    pub fn inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    pub(crate) fn inventory_mut(&mut self) -> &mut Inventory {
+        &mut self.inventory
+    }
+
+    pub fn equipment(&self) -> &HashMap<Slot, Item> {
+        &self.equipment
+    }
+
+    /// Sum of modifiers of all equipped items
+    pub fn modifiers(&self) -> Modifiers {
+        self.equipment.values().fold(Modifiers::default(), |sum, item| match item.item_type {
+            ItemType::Wearable(Wearable { modifiers, .. }) => Modifiers {
+                attack: sum.attack + modifiers.attack,
+                defense: sum.defense + modifiers.defense,
+                health: sum.health + modifiers.health,
+                mana: sum.mana + modifiers.mana,
+                speed: sum.speed + modifiers.speed,
+                action_speed: sum.action_speed + modifiers.action_speed,
+            },
+            _ => sum,
+        })
+    }
+
+    /// Holy items can't be worn by demons corrupted beyond their limit
+    pub fn can_wear(&self, wearable: &Wearable) -> bool {
+        match (self.demonicity(), wearable.max_demonicity) {
+            (Some(demonicity), Some(max)) => demonicity <= max,
+            _ => true,
+        }
+    }
+
+    /// Puts the wearable item into its slot, returns the item back if the
+    /// slot is taken
+    pub(crate) fn equip(&mut self, item: Item) -> Result<(), Item> {
+        let modifiers = match item.item_type {
+            ItemType::Wearable(ref wearable) if !self.equipment.contains_key(&wearable.slot) => {
+                self.equipment.insert(wearable.slot, item.clone());
+                wearable.modifiers
+            },
+            _ => return Err(item),
+        };
+        self.health.change_max(modifiers.health);
+        self.mana.change_max(modifiers.mana);
+        Ok(())
+    }
+
+    pub(crate) fn unequip(&mut self, slot: Slot) -> Option<Item> {
+        let item = self.equipment.remove(&slot)?;
+        if let ItemType::Wearable(Wearable { modifiers, .. }) = item.item_type {
+            self.health.change_max(-modifiers.health);
+            self.mana.change_max(-modifiers.mana);
+        }
+        Some(item)
+    }
+
+    /// Empties inventory and slots, e.g. when the creature dies
+    pub(crate) fn drop_everything(&mut self) -> Vec<Item> {
+        let mut items = self.inventory.take_all();
+        items.extend(self.equipment.drain().map(|(_, item)| item));
+        items
+    }
+
+    pub fn statuses(&self) -> &Vec<StatusEffect> {
+        &self.statuses
+    }
+
+    pub fn has_status(&self, status: Status) -> bool {
+        self.statuses.iter().any(|effect| effect.status == status)
+    }
+
+    /// Movement speed considering equipment
+    pub fn speed(&self) -> u32 {
+        cmp::max(MIN_SPEED as i32, self.speed as i32 + self.modifiers().speed) as u32
+    }
+
+    /// Speed of non-movement actions considering equipment
+    pub fn action_speed(&self) -> u32 {
+        cmp::max(MIN_SPEED as i32, self.action_speed as i32 + self.modifiers().action_speed) as u32
+    }
+
+    /// Time-points the movement with given base cost actually takes
+    pub fn move_cost(&self, base: u32) -> u32 {
+        self.apply_statuses(base * NORMAL_SPEED / self.speed())
+    }
+
+    /// Time-points the non-movement action with given base cost actually takes
+    pub fn action_cost(&self, base: u32) -> u32 {
+        self.apply_statuses(base * NORMAL_SPEED / self.action_speed())
+    }
+
+    fn apply_statuses(&self, cost: u32) -> u32 {
+        self.statuses.iter()
+            .fold(cost, |cost, effect| cost * effect.status.cost_percent() / 100)
+    }
+
+    /// Time-points left until the status wears off, 0 if there is none
+    pub fn status_remaining(&self, status: Status) -> u32 {
+        self.statuses.iter()
+            .find(|effect| effect.status == status)
+            .map_or(0, |effect| effect.remaining)
+    }
+
+    /// Returns true if the status is new for the creature
+    pub(crate) fn add_status(&mut self, effect: StatusEffect) -> bool {
+        match self.statuses.iter_mut().find(|other| other.status == effect.status) {
+            Some(other) => {
+                other.merge(&effect);
+                false
+            },
+            None => {
+                self.statuses.push(effect);
+                true
+            },
+        }
+    }
+
+    /// Passes the time for all statuses, returns damage they deal and
+    /// statuses that wore off
+    pub(crate) fn tick_statuses(&mut self, elapsed: u32) -> (u32, Vec<Status>) {
+        let damage = self.statuses.iter_mut().map(|effect| effect.tick(elapsed)).sum();
+        let expired = self.statuses.iter()
+            .filter(|effect| effect.is_over())
+            .map(|effect| effect.status)
+            .collect();
+        self.statuses.retain(|effect| !effect.is_over());
+        (damage, expired)
+    }
+
+    pub fn behaviour(&self) -> Option<Rc<dyn Behaviour>> {
+        self.behaviour.clone()
+    }
+
+    pub fn set_behaviour(&mut self, behaviour: Option<Rc<dyn Behaviour>>) {
+        self.behaviour = behaviour;
+    }
+
+    /// Broken human fights only with half of the strength
+    pub fn attack(&self) -> u32 {
+        let attack = cmp::max(0, self.attack as i32 + self.modifiers().attack) as u32;
+        if self.is_broken() {
+            attack / 2
+        } else {
+            attack
+        }
+    }
+
+    pub fn defense(&self) -> u32 {
+        cmp::max(0, self.defense as i32 + self.modifiers().defense) as u32
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.health.current > 0
+    }
+
+    pub fn demonicity(&self) -> Option<u32> {
+        match self.creature_type {
+            CreatureType::Demon { ref demonicity } => Some(demonicity.current),
+            CreatureType::Human { .. } => None,
+        }
+    }
+
+    /// Human has lost the will to fight and would rather flee, demons are
+    /// never broken
+    pub fn is_broken(&self) -> bool {
+        match self.creature_type {
+            CreatureType::Human { ref morale, .. } => morale.current < BROKEN_MORALE,
+            CreatureType::Demon { .. } => false,
+        }
+    }
+
+    /// Part of the demonic damage that would be taken considering faith
+    pub fn resist_demonic(&self, damage: u32) -> u32 {
+        match self.creature_type {
+            CreatureType::Human { ref faith, .. } =>
+                damage - damage * faith.current / (2 * MAX_FAITH),
+            CreatureType::Demon { .. } => damage,
+        }
+    }
+
+    /// Corruption stage of the demon, humans don't have any
+    pub fn corruption(&self) -> Option<Corruption> {
+        self.demonicity().map(Corruption::from_demonicity)
+    }
+
+    /// Demons are always hostile to humans, but humans notice demon
+    /// only after it is corrupted enough
+    pub fn reaction_to(&self, other: &Creature) -> Reaction {
+        match (self.corruption(), other.corruption()) {
+            (Some(_), None) => Reaction::Hostile,
+            (None, Some(corruption)) => corruption.human_reaction(),
+            _ => Reaction::Indifferent,
+        }
+    }
+
+    pub fn is_hostile_to(&self, other: &Creature) -> bool {
+        self.reaction_to(other) != Reaction::Indifferent
+    }
+
+    /// Dark spells are demons' privilege, the more powerful ones are
+    /// unlocked with corruption
+    pub fn can_cast(&self, spell: &Spell) -> bool {
+        match self.corruption() {
+            Some(corruption) => corruption >= spell.required_corruption,
+            None => spell.required_corruption == Corruption::Dormant &&
+                spell.demonicity_gain == 0,
+        }
+    }
+
+    /// Built-in spells currently available to the creature
+    pub fn known_spells(&self) -> Vec<Spell> {
+        Spell::library().into_iter().filter(|spell| self.can_cast(spell)).collect()
+    }
+
+    /// Changes demonicity of the demon (does nothing for humans) adjusting
+    /// stats. Returns previous and new corruption if it has changed
+    pub(crate) fn change_demonicity(&mut self, delta: i32)
+                                    -> Option<(Corruption, Corruption)> {
+        let (from, to) = match self.creature_type {
+            CreatureType::Demon { ref mut demonicity } => {
+                let from = Corruption::from_demonicity(demonicity.current);
+                if delta >= 0 {
+                    demonicity.increase(delta as u32);
+                } else {
+                    demonicity.decrease(delta.unsigned_abs());
+                }
+                (from, Corruption::from_demonicity(demonicity.current))
+            },
+            CreatureType::Human { .. } => return None,
+        };
+        if from == to {
+            return None
+        }
+        self.health.change_max(to.health_bonus() as i32 - from.health_bonus() as i32);
+        self.mana.change_max(to.mana_bonus() as i32 - from.mana_bonus() as i32);
+        Some((from, to))
+    }
+
+    /// Returns damage actually taken, humans lose morale along with health
+    pub(crate) fn take_damage(&mut self, damage: u32) -> u32 {
+        if let CreatureType::Human { ref mut morale, .. } = self.creature_type {
+            morale.decrease(damage);
+        }
+        self.health.decrease(damage)
+    }
+
+    /// Returns health actually restored
+    pub(crate) fn heal(&mut self, amount: u32) -> u32 {
+        self.health.increase(amount)
+    }
+
+    pub(crate) fn spend_mana(&mut self, amount: u32) {
+        debug_assert!(self.mana.current >= amount, "Mana must be checked before spending");
+        self.mana.decrease(amount);
+    }
+
     pub(crate) fn set_position(&mut self, position: Position) {
         self.position = position
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn demon(demonicity: u32) -> Creature {
+        Creature::demon(String::from("Imp"), 10, 10, Position { level: 0, x: 1, y: 1 }, demonicity)
+    }
+
+    #[test]
+    fn corruption_increases_stats() {
+        let mut imp = demon(20);
+        let change = imp.change_demonicity(10);
+        assert_eq!(change, Some((Corruption::Dormant, Corruption::Awakened)));
+        assert_eq!(imp.health().max(), 10 + Corruption::Awakened.health_bonus());
+        assert_eq!(imp.mana().max(), 10 + Corruption::Awakened.mana_bonus());
+        // bonus only makes room for more, it doesn't heal
+        assert_eq!(imp.mana().current(), 10);
+    }
+
+    #[test]
+    fn corruption_decreases_stats() {
+        let mut imp = demon(25);
+        assert_eq!(imp.change_demonicity(-1),
+                   Some((Corruption::Awakened, Corruption::Dormant)));
+        assert_eq!(imp.health().max(), 10);
+        assert_eq!(imp.health().current(), 10);
+    }
+
+    fn human(profession: Profession) -> Creature {
+        Creature::human(String::from("Hans"), 10, 0, Position { level: 0, x: 1, y: 1 }, profession)
+    }
+
+    #[test]
+    fn faith_resists_demonic_damage() {
+        assert!(human(Profession::Priest).resist_demonic(10) <
+                human(Profession::Jailer).resist_demonic(10));
+        assert_eq!(demon(0).resist_demonic(10), 10);
+    }
+
+    #[test]
+    fn damage_breaks_morale() {
+        let mut guard = human(Profession::Guard);
+        let attack = guard.attack();
+        guard.take_damage(5);
+        assert!(!guard.is_broken());
+        guard.take_damage(40);
+        assert!(guard.is_broken());
+        assert!(guard.attack() < attack);
+    }
+
+    #[test]
+    fn humans_notice_corruption() {
+        let guard = human(Profession::Guard);
+        assert_eq!(guard.reaction_to(&demon(0)), Reaction::Indifferent);
+        assert_eq!(guard.reaction_to(&demon(30)), Reaction::Hostile);
+        assert_eq!(guard.reaction_to(&demon(MAX_DEMONICITY)), Reaction::Terrified);
+        assert_eq!(demon(0).reaction_to(&guard), Reaction::Hostile);
+    }
+
+    #[test]
+    fn statuses_change_action_cost() {
+        let mut imp = demon(0);
+        imp.add_status(StatusEffect::new(Status::Hasted, 300, 0));
+        assert_eq!(imp.action_cost(100), 50);
+        imp.add_status(StatusEffect::new(Status::Slowed, 200, 0));
+        assert_eq!(imp.action_cost(100), 100);
+        assert_eq!(imp.status_remaining(Status::Slowed), 200);
+        imp.tick_statuses(200);
+        assert_eq!(imp.action_cost(100), 50);
+        assert_eq!(imp.tick_statuses(100), (0, vec![Status::Hasted]));
+        assert_eq!(imp.action_cost(100), 100);
+    }
+
+    #[test]
+    fn speed_changes_costs() {
+        let imp = demon(0).with_speed(200, 50);
+        assert_eq!(imp.move_cost(100), 50);
+        assert_eq!(imp.action_cost(100), 200);
+        assert_eq!(demon(0).with_speed(0, 0).action_cost(100), 1000);
+    }
+
+    #[test]
+    fn spells_unlocked_by_corruption() {
+        let hellfire = Spell::hellfire();
+        assert!(!demon(0).can_cast(&hellfire));
+        assert!(demon(MAX_DEMONICITY).can_cast(&hellfire));
+    }
+}
@@ -0,0 +1,102 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+
+/// Demonicity gained by demon for every killed human
+pub const HUMAN_KILL_GAIN: u32 = 5;
+/// Time-points needed for demonicity to decay by one point
+pub const DECAY_PERIOD: u32 = 1000;
+
+/// Stage of demon's corruption, determined by its demonicity
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Corruption {
+    /// Demon can still be taken for a human
+    Dormant,
+    Awakened,
+    Ascended,
+    /// Pure demonic essence, humans are terrified of it
+    Unbound,
+}
+
+/// How one creature treats another
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reaction {
+    Indifferent,
+    Hostile,
+    /// Hostile, but would rather run away than fight
+    Terrified,
+}
+
+impl Corruption {
+    pub fn from_demonicity(demonicity: u32) -> Corruption {
+        match demonicity {
+            0..=24 => Corruption::Dormant,
+            25..=49 => Corruption::Awakened,
+            50..=74 => Corruption::Ascended,
+            _ => Corruption::Unbound,
+        }
+    }
+
+    /// Bonus to max health of the demon
+    pub fn health_bonus(self) -> u32 {
+        match self {
+            Corruption::Dormant => 0,
+            Corruption::Awakened => 10,
+            Corruption::Ascended => 20,
+            Corruption::Unbound => 40,
+        }
+    }
+
+    /// Bonus to max mana of the demon
+    pub fn mana_bonus(self) -> u32 {
+        match self {
+            Corruption::Dormant => 0,
+            Corruption::Awakened => 10,
+            Corruption::Ascended => 25,
+            Corruption::Unbound => 50,
+        }
+    }
+
+    /// How humans react on the demon with this corruption
+    pub fn human_reaction(self) -> Reaction {
+        match self {
+            Corruption::Dormant => Reaction::Indifferent,
+            Corruption::Awakened | Corruption::Ascended => Reaction::Hostile,
+            Corruption::Unbound => Reaction::Terrified,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corruption_thresholds() {
+        assert_eq!(Corruption::from_demonicity(0), Corruption::Dormant);
+        assert_eq!(Corruption::from_demonicity(25), Corruption::Awakened);
+        assert_eq!(Corruption::from_demonicity(74), Corruption::Ascended);
+        assert_eq!(Corruption::from_demonicity(100), Corruption::Unbound);
+    }
+}
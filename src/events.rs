@@ -0,0 +1,92 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::rc::Weak;
+
+use actions::{Action, ActionError};
+use world::CreatureRef;
+use utils::Position;
+use demonicity::Corruption;
+use status::Status;
+use creatures::Creature;
+
+/// Something that happened in the [`World`] while it was advancing
+pub enum Event {
+    /// Action passed validation and was applied to the world
+    ActionCommitted(Action),
+    /// Action was rejected, its actor lost the time anyway
+    ActionFailed(Action, ActionError),
+    /// Creature lost some health
+    Damaged { creature: Weak<CreatureRef>, amount: u32 },
+    /// Creature restored some health
+    Healed { creature: Weak<CreatureRef>, amount: u32 },
+    /// Creature got new status effect
+    StatusApplied { creature: Weak<CreatureRef>, status: Status },
+    /// Status effect of the creature wore off
+    StatusExpired { creature: Weak<CreatureRef>, status: Status },
+    /// Timed explosion went off, damage is reported separately
+    Exploded { position: Position, radius: usize },
+    /// Creature was brought into the world by reinforcements
+    Spawned(Weak<CreatureRef>),
+    /// Creature was removed from the world, leaving a corpse
+    Died { name: String, position: Position },
+    /// Creature went through the stairs
+    LevelChanged { creature: Weak<CreatureRef>, from: usize, to: usize },
+    /// New level was generated, holds its index
+    LevelGenerated(usize),
+    /// Demon's demonicity has been changed, holds the new value
+    DemonicityChanged { creature: Weak<CreatureRef>, demonicity: u32 },
+    /// Demon has crossed corruption threshold
+    CorruptionChanged { creature: Weak<CreatureRef>, from: Corruption, to: Corruption },
+}
+
+/// Something that happens by itself when its time comes, scheduled with
+/// [`World::schedule_event`]
+#[derive(Debug)]
+pub enum TimedEvent {
+    /// Damages every creature within radius around the position
+    Explosion { position: Position, radius: usize, damage: u32 },
+    /// Brings new creatures into the world, ones whose tile is taken don't come
+    Reinforcements(Vec<Creature>),
+}
+
+/// Identifies scheduled [`TimedEvent`], so it can be cancelled
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimedEventId(pub(crate) u64);
+
+/// Reason why [`World::turn`] returned control to the caller
+#[derive(Debug, PartialEq)]
+pub enum TurnStatus {
+    /// Main character has no action assigned and the world can't go on without it
+    AwaitingInput,
+    /// There is nothing scheduled at all
+    QueueIsEmpty,
+    /// Main character is dead, game is over
+    MainCharacterDied,
+}
+
+/// Everything that happened during one call of [`World::turn`]
+pub struct TurnResult {
+    pub events: Vec<Event>,
+    pub status: TurnStatus,
+}
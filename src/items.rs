@@ -0,0 +1,240 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use map::tiles::LockId;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ItemType {
+    /// Opens the locked door with the same lock
+    Key(LockId),
+    /// Restores given amount of health when used
+    HealingPotion(u32),
+    /// Can be equipped into the slot
+    Wearable(Wearable),
+    /// Has no use, but can be carried around
+    Junk,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Slot {
+    Weapon,
+    Armor,
+    Ring,
+    Talisman,
+}
+
+/// Changes of creature's stats while the item is equipped
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Modifiers {
+    pub attack: i32,
+    pub defense: i32,
+    pub health: i32,
+    pub mana: i32,
+    /// Added to movement speed, heavy armor slows down
+    pub speed: i32,
+    /// Added to speed of all other actions
+    pub action_speed: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wearable {
+    pub slot: Slot,
+    pub modifiers: Modifiers,
+    /// Holy items burn demons with higher demonicity, humans can always
+    /// wear anything
+    pub max_demonicity: Option<u32>,
+}
+
+impl Wearable {
+    pub fn new(slot: Slot, modifiers: Modifiers) -> Wearable {
+        Wearable { slot, modifiers, max_demonicity: None }
+    }
+
+    pub fn holy(mut self, max_demonicity: u32) -> Wearable {
+        self.max_demonicity = Some(max_demonicity);
+        self
+    }
+}
+
+/// Stack of the same items, non-stackable ones always come alone
+#[derive(Clone, Debug)]
+pub struct Item {
+    pub name: String,
+    pub item_type: ItemType,
+    /// Weight of a single item in the stack
+    pub weight: u32,
+    pub count: u32,
+    /// Maximum items in one stack
+    pub max_stack: u32,
+}
+
+impl Item {
+    pub fn new(name: String, item_type: ItemType, weight: u32) -> Item {
+        Item { name, item_type, weight, count: 1, max_stack: 1 }
+    }
+
+    pub fn key(lock: LockId) -> Item {
+        Item::new(String::from("Key"), ItemType::Key(lock), 1)
+    }
+
+    pub fn healing_potion(amount: u32) -> Item {
+        Item::new(String::from("Healing potion"), ItemType::HealingPotion(amount), 2)
+            .stackable(10)
+    }
+
+    pub fn wearable(name: String, weight: u32, wearable: Wearable) -> Item {
+        Item::new(name, ItemType::Wearable(wearable), weight)
+    }
+
+    /// Allows up to `max_stack` items to share one inventory slot
+    pub fn stackable(mut self, max_stack: u32) -> Item {
+        self.max_stack = max_stack;
+        self
+    }
+
+    pub fn with_count(mut self, count: u32) -> Item {
+        self.count = count;
+        self
+    }
+
+    pub fn total_weight(&self) -> u32 {
+        self.weight * self.count
+    }
+
+    /// Items are the same and can be put into one stack
+    pub fn stacks_with(&self, other: &Item) -> bool {
+        self.max_stack > 1 && self.name == other.name &&
+            self.item_type == other.item_type && self.weight == other.weight
+    }
+
+    /// Takes up to `count` items off the stack
+    pub(crate) fn split(&mut self, count: u32) -> Item {
+        let count = ::std::cmp::min(count, self.count);
+        self.count -= count;
+        Item { count, ..self.clone() }
+    }
+}
+
+/// Items carried by the creature, their total weight is limited by capacity
+#[derive(Debug)]
+pub struct Inventory {
+    items: Vec<Item>,
+    capacity: u32,
+}
+
+impl Inventory {
+    pub fn new(capacity: u32) -> Inventory {
+        Inventory { items: vec![], capacity }
+    }
+
+    pub fn items(&self) -> &Vec<Item> {
+        &self.items
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    pub fn weight(&self) -> u32 {
+        self.items.iter().map(Item::total_weight).sum()
+    }
+
+    pub fn can_carry(&self, item: &Item) -> bool {
+        self.weight() + item.total_weight() <= self.capacity
+    }
+
+    pub fn has_key(&self, lock: LockId) -> bool {
+        self.items.iter().any(|item| item.item_type == ItemType::Key(lock))
+    }
+
+    /// Puts the item into the inventory filling existing stacks first.
+    /// Returns the item back if it is too heavy
+    pub(crate) fn add(&mut self, mut item: Item) -> Result<(), Item> {
+        if !self.can_carry(&item) {
+            return Err(item)
+        }
+        for stack in self.items.iter_mut() {
+            if stack.stacks_with(&item) {
+                let moved = item.split(stack.max_stack.saturating_sub(stack.count));
+                stack.count += moved.count;
+            }
+        }
+        while item.count > 0 {
+            let max_stack = item.max_stack;
+            let stack = item.split(max_stack);
+            self.items.push(stack);
+        }
+        Ok(())
+    }
+
+    /// Empties the inventory, e.g. when its owner dies
+    pub(crate) fn take_all(&mut self) -> Vec<Item> {
+        self.items.drain(..).collect()
+    }
+
+    /// Takes up to `count` items from the slot, empty slots are removed
+    pub(crate) fn take(&mut self, index: usize, count: u32) -> Option<Item> {
+        let item = self.items.get_mut(index)?.split(count);
+        if self.items[index].count == 0 {
+            self.items.remove(index);
+        }
+        Some(item)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn potions_stack() {
+        let mut inventory = Inventory::new(100);
+        inventory.add(Item::healing_potion(5).with_count(7)).unwrap();
+        inventory.add(Item::healing_potion(5).with_count(7)).unwrap();
+        let counts: Vec<u32> = inventory.items().iter().map(|item| item.count).collect();
+        assert_eq!(counts, vec![10, 4]);
+        assert_eq!(inventory.weight(), 28);
+    }
+
+    #[test]
+    fn keys_dont_stack() {
+        let mut inventory = Inventory::new(100);
+        inventory.add(Item::key(1)).unwrap();
+        inventory.add(Item::key(1)).unwrap();
+        assert_eq!(inventory.items().len(), 2);
+        assert!(inventory.has_key(1));
+        assert!(!inventory.has_key(2));
+    }
+
+    #[test]
+    fn capacity_limit() {
+        let mut inventory = Inventory::new(5);
+        assert!(inventory.add(Item::healing_potion(5).with_count(3)).is_err());
+        inventory.add(Item::healing_potion(5).with_count(2)).unwrap();
+        assert_eq!(inventory.take(0, 1).unwrap().count, 1);
+        assert_eq!(inventory.take(0, 5).unwrap().count, 1);
+        assert!(inventory.items().is_empty());
+        assert!(inventory.take(0, 1).is_none());
+    }
+}
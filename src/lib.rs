@@ -4,7 +4,35 @@ mod creatures;
 mod actions;
 mod utils;
 mod scheduler;
+mod events;
+mod spells;
+mod demonicity;
+mod ai;
+mod items;
+mod status;
+mod rng;
 
-pub use world::World;
-pub use map::tiles::Tile;
-pub use map::tiles::TileType;
+pub use world::{World, DecisionSource, Idle};
+pub use events::{Event, TimedEvent, TimedEventId, TurnResult, TurnStatus};
+pub use world::CreatureRef;
+pub use actions::{Action, ActionError};
+pub use creatures::{Creature, CreatureType, Points, Profession};
+pub use utils::{Direction, Position};
+pub use ai::{Behaviour, Wander, ChasePlayer, FleeAtLowHealth, Guard};
+pub use demonicity::{Corruption, Reaction};
+pub use items::{Item, ItemType, Inventory, Slot, Modifiers, Wearable};
+pub use status::{Status, StatusEffect, STATUS_TICK};
+pub use spells::{Spell, SpellEffect, SpellTarget, Targeting};
+pub use map::tiles::{Tile, Corpse};
+pub use map::tiles::{TileType, LockId};
+pub use map::sound::can_hear;
+pub use map::tiles::Map;
+pub use rng::Rng;
+pub use map::generators::{MapGenerator, SimpleBoxGenerator, BspGenerator, CaveGenerator};
+pub use map::generators::{Vault, PlaceVaults};
+pub use map::generators::{Stage, Pipeline, ConnectRegions, CullDoors, PlaceStairs, Pillars};
+pub use map::ascii::AsciiError;
+pub use map::analysis::{Regions, LevelReport, chokepoints, spawn_point};
+pub use map::fov::FieldOfView;
+pub use map::pathfinding::{DijkstraMap, Goal};
+pub use map::memory::{MapMemory, LevelMemory, RememberedTile};
@@ -0,0 +1,297 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::cmp;
+use std::collections::VecDeque;
+
+use utils::*;
+use super::tiles::*;
+use super::pathfinding::{self, DijkstraMap};
+
+/// Connected areas of walkable tiles labeled by flood fill. Tiles are
+/// connected orthogonally, closed doors count as walkable unless locked
+pub struct Regions {
+    labels: Vec<Vec<Option<usize>>>,
+    tiles: Vec<Vec<(usize, usize)>>,
+}
+
+/// Orthogonal neighbours of the tile inside the map that can be walked on
+fn walkable_neighbours(map: &Map, x: usize, y: usize, walkable: fn(&Tile) -> bool)
+                       -> Vec<(usize, usize)> {
+    let Map(ref tiles) = *map;
+    [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)].iter()
+        .cloned()
+        .filter(|&(x, y)| x < tiles.len() && y < tiles[0].len() && walkable(&tiles[x][y]))
+        .collect()
+}
+
+/// Walkable for someone having all the keys
+fn is_walkable_with_keys(tile: &Tile) -> bool {
+    pathfinding::is_walkable(tile) || matches!(tile.tile_type, TileType::Door { .. })
+}
+
+impl Regions {
+    pub fn new(map: &Map) -> Regions {
+        Regions::flood_fill(map, pathfinding::is_walkable)
+    }
+
+    /// Regions for someone having all the keys, locked doors don't split
+    /// the level. Generators use it to keep locked rooms apart from the rest
+    pub fn through_locks(map: &Map) -> Regions {
+        Regions::flood_fill(map, is_walkable_with_keys)
+    }
+
+    fn flood_fill(map: &Map, walkable: fn(&Tile) -> bool) -> Regions {
+        let Map(ref tiles) = *map;
+        let mut labels = vec![vec![None; tiles[0].len()]; tiles.len()];
+        let mut regions = vec![];
+        for (x, y, tile) in map.tiles() {
+            if labels[x][y].is_some() || !walkable(tile) {
+                continue
+            }
+            let label = regions.len();
+            let mut region = vec![];
+            let mut queue = VecDeque::new();
+            labels[x][y] = Some(label);
+            queue.push_back((x, y));
+            while let Some((x, y)) = queue.pop_front() {
+                region.push((x, y));
+                for (x, y) in walkable_neighbours(map, x, y, walkable) {
+                    if labels[x][y].is_none() {
+                        labels[x][y] = Some(label);
+                        queue.push_back((x, y));
+                    }
+                }
+            }
+            regions.push(region);
+        }
+        Regions { labels, tiles: regions }
+    }
+
+    pub fn count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Region the tile belongs to, `None` for walls, locked doors and
+    /// tiles outside the map
+    pub fn label(&self, x: usize, y: usize) -> Option<usize> {
+        self.labels.get(x).and_then(|column| column.get(y)).and_then(|label| *label)
+    }
+
+    /// Tiles of the region in the order flood fill has reached them
+    pub fn tiles(&self, label: usize) -> &[(usize, usize)] {
+        &self.tiles[label]
+    }
+
+    /// Region with the most tiles, the earliest one among equal
+    pub fn largest(&self) -> Option<usize> {
+        (0..self.count()).max_by_key(|&label| (self.tiles[label].len(), cmp::Reverse(label)))
+    }
+}
+
+/// Walkable tiles that split their region in parts if blocked, like
+/// corridors and doorways. Found as articulation points of the tiles graph
+pub fn chokepoints(map: &Map) -> Vec<(usize, usize)> {
+    let Map(ref tiles) = *map;
+    let mut order = vec![vec![None; tiles[0].len()]; tiles.len()];
+    let mut low = vec![vec![0; tiles[0].len()]; tiles.len()];
+    let mut is_chokepoint = vec![vec![false; tiles[0].len()]; tiles.len()];
+    let mut counter = 0;
+    for (x, y, tile) in map.tiles() {
+        if order[x][y].is_some() || !pathfinding::is_walkable(tile) {
+            continue
+        }
+        order[x][y] = Some(counter);
+        low[x][y] = counter;
+        counter += 1;
+        let mut root_children = 0;
+        // depth-first search without recursion: tiles of the current path
+        // with the index of the neighbour to try next
+        let mut stack = vec![((x, y), 0)];
+        while let Some(&((x, y), next)) = stack.last() {
+            let neighbours = walkable_neighbours(map, x, y, pathfinding::is_walkable);
+            if next < neighbours.len() {
+                let depth = stack.len();
+                stack[depth - 1].1 += 1;
+                let parent = if depth > 1 { Some(stack[depth - 2].0) } else { None };
+                let (nx, ny) = neighbours[next];
+                match order[nx][ny] {
+                    None => {
+                        order[nx][ny] = Some(counter);
+                        low[nx][ny] = counter;
+                        counter += 1;
+                        stack.push(((nx, ny), 0));
+                    },
+                    Some(visited) if parent != Some((nx, ny)) =>
+                        low[x][y] = cmp::min(low[x][y], visited),
+                    _ => (),
+                }
+                continue
+            }
+            stack.pop();
+            match stack.len() {
+                0 => (),
+                1 => root_children += 1,
+                _ => {
+                    let (px, py) = stack[stack.len() - 1].0;
+                    if Some(low[x][y]) >= order[px][py] {
+                        is_chokepoint[px][py] = true;
+                    }
+                },
+            }
+            if let Some(&((px, py), _)) = stack.last() {
+                low[px][py] = cmp::min(low[px][py], low[x][y]);
+            }
+        }
+        is_chokepoint[x][y] = root_children > 1;
+    }
+    map.tiles()
+        .filter(|&(x, y, _)| is_chokepoint[x][y])
+        .map(|(x, y, _)| (x, y))
+        .collect()
+}
+
+/// Ground tile of the largest region closest to given coordinates, safe
+/// place for creatures to appear on the level
+pub fn spawn_point(map: &Map, level: usize, x: usize, y: usize) -> Option<Position> {
+    let regions = Regions::new(map);
+    let largest = regions.largest()?;
+    let origin = Position { level, x, y };
+    regions.tiles(largest).iter()
+        .filter(|&&(x, y)| matches!(map.get(x, y).tile_type, TileType::Ground))
+        .map(|&(x, y)| Position { level, x, y })
+        .min_by_key(|position| (position.distance(&origin), position.x, position.y))
+}
+
+/// What the level looks like from where creatures get to it. Generators
+/// and tests use it to reject broken levels
+pub struct LevelReport {
+    pub regions: Regions,
+    pub chokepoints: Vec<(usize, usize)>,
+    /// Stairs no path from the spawn leads to
+    pub unreachable_stairs: Vec<Position>,
+    /// Time-points needed to walk from the spawn to every reachable stairs
+    pub exit_distances: Vec<(Position, i32)>,
+}
+
+impl LevelReport {
+    pub fn new(map: &Map, spawn: Position) -> LevelReport {
+        let level = spawn.level;
+        // stairs must be reachable whatever the corner cutting rule is
+        let distances = DijkstraMap::new(map, level, &[spawn], false);
+        let mut unreachable_stairs = vec![];
+        let mut exit_distances = vec![];
+        let stairs = map.tiles()
+            .filter(|&(_, _, tile)| matches!(tile.tile_type, TileType::Stairs { .. }))
+            .map(|(x, y, _)| Position { level, x, y });
+        for stairs in stairs {
+            match distances.distance(stairs) {
+                Some(distance) => exit_distances.push((stairs, distance)),
+                None => unreachable_stairs.push(stairs),
+            }
+        }
+        LevelReport {
+            regions: Regions::new(map),
+            chokepoints: chokepoints(map),
+            unreachable_stairs,
+            exit_distances,
+        }
+    }
+
+    /// All the stairs can be reached from the spawn
+    pub fn is_valid(&self) -> bool {
+        self.unreachable_stairs.is_empty()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rooms() -> Map {
+        Map::from_ascii("
+            ###########
+            #...#.....#
+            #.>.+..<..#
+            #...#.....#
+            ##1########
+            #...#######
+            #.>.#######
+            ###########").unwrap()
+    }
+
+    #[test]
+    fn labeling_regions() {
+        let regions = Regions::new(&rooms());
+        assert_eq!(regions.count(), 2);
+        assert_eq!(regions.label(2, 2), regions.label(8, 2));
+        assert!(regions.label(2, 6).is_some());
+        assert_ne!(regions.label(2, 2), regions.label(2, 6));
+        assert_eq!(regions.label(4, 4), None);
+        assert_eq!(regions.label(0, 0), None);
+        assert_eq!(regions.label(40, 2), None);
+        assert_eq!(regions.largest(), regions.label(2, 2));
+        assert_eq!(regions.tiles(regions.largest().unwrap()).len(), 9 + 1 + 15);
+        assert_eq!(Regions::through_locks(&rooms()).count(), 1);
+    }
+
+    #[test]
+    fn finding_chokepoints() {
+        let map = Map::from_ascii("
+            #########
+            #...#...#
+            #.......#
+            #...#...#
+            #########").unwrap();
+        assert_eq!(chokepoints(&map), vec![(3, 2), (4, 2), (5, 2)]);
+
+        let open = Map::from_ascii("
+            #####
+            #...#
+            #...#
+            #####").unwrap();
+        assert!(chokepoints(&open).is_empty());
+        let corridor = Map::from_ascii("#....#").unwrap();
+        assert_eq!(chokepoints(&corridor), vec![(2, 0), (3, 0)]);
+    }
+
+    #[test]
+    fn stairs_behind_locked_door() {
+        let map = rooms();
+        let spawn = spawn_point(&map, 0, 6, 2).unwrap();
+        assert_eq!((spawn.x, spawn.y), (6, 2));
+        let report = LevelReport::new(&map, spawn);
+        assert!(!report.is_valid());
+        assert_eq!(report.unreachable_stairs, vec![Position { level: 0, x: 2, y: 6 }]);
+        let reachable: Vec<_> = report.exit_distances.iter()
+            .map(|&(stairs, distance)| ((stairs.x, stairs.y), distance))
+            .collect();
+        let step = Direction::Right.step_cost() as i32;
+        assert_eq!(reachable, vec![((2, 2), 4 * step), ((7, 2), step)]);
+        assert!(report.chokepoints.contains(&(4, 2)));
+
+        // spawn always gets to the largest part, even if it's asked elsewhere
+        assert_eq!(spawn_point(&map, 0, 2, 5).map(|spawn| spawn.y), Some(3));
+    }
+}
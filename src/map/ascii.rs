@@ -0,0 +1,130 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::char;
+
+use super::tiles::*;
+
+/// Reason why the text can't be turned into the map
+#[derive(Debug, PartialEq)]
+pub enum AsciiError {
+    /// There are no rows at all
+    Empty,
+    /// Row with given index differs in length from the first one
+    RaggedRow(usize),
+    /// Symbol doesn't stand for any tile
+    UnknownSymbol { symbol: char, x: usize, y: usize },
+}
+
+/// `#` wall, `.` ground, `+` closed door, `'` open door, digit is a door
+/// locked with that lock, `>` and `<` are stairs down and up
+pub(crate) fn tile_type(symbol: char) -> Option<TileType> {
+    match symbol {
+        '#' => Some(TileType::Wall),
+        '.' => Some(TileType::Ground),
+        '+' => Some(TileType::Door { closed: true, locked: false, lock: None }),
+        '\'' => Some(TileType::Door { closed: false, locked: false, lock: None }),
+        '>' => Some(TileType::Stairs { down: true }),
+        '<' => Some(TileType::Stairs { down: false }),
+        digit => digit.to_digit(10)
+            .map(|lock| TileType::Door { closed: true, locked: true, lock: Some(lock) }),
+    }
+}
+
+fn symbol(tile_type: &TileType) -> char {
+    match *tile_type {
+        TileType::Wall => '#',
+        TileType::Ground => '.',
+        TileType::Door { locked: true, lock: Some(lock), .. } =>
+            char::from_digit(lock, 10).unwrap_or('+'),
+        TileType::Door { closed: true, .. } => '+',
+        TileType::Door { closed: false, .. } => '\'',
+        TileType::Stairs { down: true } => '>',
+        TileType::Stairs { down: false } => '<',
+    }
+}
+
+/// Non-empty rows of the text with surrounding whitespace trimmed
+pub(crate) fn rows(text: &str) -> Vec<Vec<char>> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| line.chars().collect())
+        .collect()
+}
+
+impl Map {
+    /// Parses the map drawn with tile symbols, one text line is one row
+    /// (`y`), leading and trailing whitespace of lines is ignored
+    pub fn from_ascii(text: &str) -> Result<Map, AsciiError> {
+        let rows = rows(text);
+        if rows.is_empty() {
+            return Err(AsciiError::Empty)
+        }
+        if let Some(index) = rows.iter().position(|row| row.len() != rows[0].len()) {
+            return Err(AsciiError::RaggedRow(index))
+        }
+        let mut tiles = vec![vec![]; rows[0].len()];
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &symbol) in row.iter().enumerate() {
+                let tile_type = tile_type(symbol)
+                    .ok_or(AsciiError::UnknownSymbol { symbol, x, y })?;
+                tiles[x].push(Tile::new(tile_type));
+            }
+        }
+        Ok(Map::new(tiles))
+    }
+
+    /// Draws the map with the same symbols [`Map::from_ascii`] understands
+    pub fn to_ascii(&self) -> String {
+        let Map(ref tiles) = *self;
+        (0..tiles[0].len())
+            .map(|y| tiles.iter().map(|column| symbol(&column[y].tile_type)).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_and_drawing() {
+        let text = "#####\n#.<'#\n#>+3#\n#####";
+        let map = Map::from_ascii(text).unwrap();
+        assert!(matches!(map.get(3, 1).tile_type, TileType::Door { closed: false, .. }));
+        assert!(matches!(map.get(3, 2).tile_type, TileType::Door { lock: Some(3), .. }));
+        assert!(matches!(map.get(1, 2).tile_type, TileType::Stairs { down: true }));
+        assert_eq!(map.to_ascii(), text);
+    }
+
+    #[test]
+    fn invalid_text() {
+        assert_eq!(Map::from_ascii("  \n").err(), Some(AsciiError::Empty));
+        assert_eq!(Map::from_ascii("###\n##").err(), Some(AsciiError::RaggedRow(1)));
+        assert_eq!(Map::from_ascii("#.\n.x").err(),
+                   Some(AsciiError::UnknownSymbol { symbol: 'x', x: 1, y: 1 }));
+    }
+}
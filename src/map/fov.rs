@@ -0,0 +1,186 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use utils::*;
+use super::tiles::*;
+
+/// Multipliers transforming coordinates of the first octant into the others
+const OCTANTS: [(isize, isize, isize, isize); 8] = [
+    (1, 0, 0, 1), (0, 1, 1, 0), (0, -1, 1, 0), (-1, 0, 0, 1),
+    (-1, 0, 0, -1), (0, -1, -1, 0), (0, 1, -1, 0), (1, 0, 0, -1),
+];
+
+/// Tiles visible from some position on a single level
+pub struct FieldOfView {
+    origin: Position,
+    visible: Vec<Vec<bool>>,
+}
+
+impl FieldOfView {
+    /// Computes visible tiles using recursive shadowcasting. Tiles farther
+    /// than radius are never visible
+    pub fn new(map: &Map, origin: Position, radius: usize) -> FieldOfView {
+        let Map(ref tiles) = *map;
+        let mut fov = FieldOfView {
+            origin,
+            visible: vec![vec![false; tiles[0].len()]; tiles.len()],
+        };
+        if origin.x < tiles.len() && origin.y < tiles[0].len() {
+            fov.visible[origin.x][origin.y] = true;
+        }
+        for &octant in OCTANTS.iter() {
+            fov.cast_light(map, radius as isize, 1, 1.0, 0.0, octant);
+        }
+        fov
+    }
+
+    pub fn origin(&self) -> Position {
+        self.origin
+    }
+
+    pub fn is_visible(&self, position: Position) -> bool {
+        position.level == self.origin.level &&
+            self.visible.get(position.x)
+                .and_then(|column| column.get(position.y))
+                .cloned()
+                .unwrap_or(false)
+    }
+
+    /// All visible positions, in no particular order
+    pub fn visible_positions(&self) -> Vec<Position> {
+        let level = self.origin.level;
+        self.visible.iter()
+            .enumerate()
+            .flat_map(|(x, column)| column.iter()
+                      .enumerate()
+                      .filter(|&(_, &visible)| visible)
+                      .map(move |(y, _)| Position { level, x, y }))
+            .collect()
+    }
+
+    fn tile(map: &Map, x: isize, y: isize) -> Option<&Tile> {
+        let Map(ref tiles) = *map;
+        if x < 0 || y < 0 || x as usize >= tiles.len() || y as usize >= tiles[0].len() {
+            None
+        } else {
+            Some(&tiles[x as usize][y as usize])
+        }
+    }
+
+    /// Scans single octant row by row starting from `row`, recursing into
+    /// the part of the row still lit after meeting the obstacle
+    fn cast_light(&mut self,
+                  map: &Map,
+                  radius: isize,
+                  row: isize,
+                  mut start: f64,
+                  end: f64,
+                  (xx, xy, yx, yy): (isize, isize, isize, isize)) {
+        if start < end {
+            return
+        }
+        let (ox, oy) = (self.origin.x as isize, self.origin.y as isize);
+        let mut new_start = 0.0;
+        for j in row..=radius {
+            let dy = -j;
+            let mut blocked = false;
+            for dx in -j..=0 {
+                let left_slope = (dx as f64 - 0.5) / (dy as f64 + 0.5);
+                let right_slope = (dx as f64 + 0.5) / (dy as f64 - 0.5);
+                if start < right_slope {
+                    continue
+                } else if end > left_slope {
+                    break
+                }
+
+                let (x, y) = (ox + dx * xx + dy * xy, oy + dx * yx + dy * yy);
+                let tile = FieldOfView::tile(map, x, y);
+                if tile.is_some() && dx * dx + dy * dy <= radius * radius {
+                    self.visible[x as usize][y as usize] = true;
+                }
+
+                let blocks = tile.map(|tile| tile.blocks_sight()).unwrap_or(true);
+                if blocked {
+                    if blocks {
+                        new_start = right_slope;
+                    } else {
+                        blocked = false;
+                        start = new_start;
+                    }
+                } else if blocks && j < radius {
+                    blocked = true;
+                    self.cast_light(map, radius, j + 1, start, left_slope, (xx, xy, yx, yy));
+                    new_start = right_slope;
+                }
+            }
+            if blocked {
+                break
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map::generators::*;
+    use rng::Rng;
+
+    fn at(x: usize, y: usize) -> Position {
+        Position { level: 0, x, y }
+    }
+
+    #[test]
+    fn open_room_visible() {
+        let map = SimpleBoxGenerator::new(10, 10).generate(&mut Rng::new(0));
+        let fov = FieldOfView::new(&map, at(5, 5), 10);
+        assert!(fov.is_visible(at(1, 1)));
+        assert!(fov.is_visible(at(0, 0)), "Walls themselves are visible");
+        assert!(!fov.is_visible(Position { level: 1, ..at(1, 1) }));
+    }
+
+    #[test]
+    fn radius_limits_sight() {
+        let map = SimpleBoxGenerator::new(20, 20).generate(&mut Rng::new(0));
+        let fov = FieldOfView::new(&map, at(2, 2), 3);
+        assert!(fov.is_visible(at(4, 4)));
+        assert!(!fov.is_visible(at(8, 2)));
+    }
+
+    #[test]
+    fn walls_and_closed_doors_block_sight() {
+        let mut map = SimpleBoxGenerator::new(10, 10).generate(&mut Rng::new(0));
+        for y in 1..9 {
+            map.0[5][y] = Tile::new(TileType::Wall);
+        }
+        map.0[5][4] = Tile::new(TileType::Door { closed: true, locked: false, lock: None });
+        let fov = FieldOfView::new(&map, at(2, 4), 10);
+        assert!(fov.is_visible(at(5, 4)));
+        assert!(!fov.is_visible(at(7, 4)));
+
+        map.0[5][4] = Tile::new(TileType::Door { closed: false, locked: false, lock: None });
+        let fov = FieldOfView::new(&map, at(2, 4), 10);
+        assert!(fov.is_visible(at(7, 4)));
+    }
+}
@@ -1,62 +0,0 @@
-/*
-    MIT License
-
-    Copyright (c) 2017 Dan Hawk
-
-    Permission is hereby granted, free of charge, to any person obtaining a copy
-    of this software and associated documentation files (the "Software"), to deal
-    in the Software without restriction, including without limitation the rights
-    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
-    copies of the Software, and to permit persons to whom the Software is
-    furnished to do so, subject to the following conditions:
-
-    The above copyright notice and this permission notice shall be included in all
-    copies or substantial portions of the Software.
-
-    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
-    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
-    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
-    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
-    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
-    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
-    SOFTWARE.
-    */
-
-use super::tiles::*;
-
-pub trait MapGenerator {
-    fn generate(&self) -> Map;
-}
-
-/// Always generate the same simple map. Just for testing and debugging
-pub struct SimpleBoxGenerator {
-    width: usize,
-    height: usize,
-}
-
-impl SimpleBoxGenerator {
-    pub fn new(width: usize, height: usize) -> SimpleBoxGenerator {
-        SimpleBoxGenerator { width, height }
-    }
-}
-
-impl MapGenerator for SimpleBoxGenerator {
-
-    fn generate(&self) -> Map {
-        let width = self.width;
-        let height = self.height;
-
-        let mut map = vec![vec![Tile::new(TileType::Ground); height]; width];
-        for x in 0..self.width {
-            map[x][0] = Tile::new(TileType::Wall);
-            map[x][height - 1] = Tile::new(TileType::Wall);
-        }
-
-        for y in 0..self.height {
-            map[0][y] = Tile::new(TileType::Wall);
-            map[width - 1][y] = Tile::new(TileType::Wall);
-        }
-
-        Map::new(map)
-    }
-}
\ No newline at end of file
@@ -0,0 +1,221 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::cmp;
+
+use rng::Rng;
+use map::tiles::*;
+use super::{MapGenerator, dig_tunnel, is_doorway};
+
+const DEFAULT_MIN_ROOM_SIZE: usize = 4;
+const DEFAULT_MAX_ROOM_SIZE: usize = 10;
+const DEFAULT_DEPTH: u32 = 4;
+
+/// Rectangle of tiles, x and y are of its top-left corner
+#[derive(Clone, Copy, Debug)]
+struct Rect {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Rect {
+    fn center(&self) -> (usize, usize) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// Splits the map in two recursively and puts a room into every resulting
+/// partition, sibling partitions are joined with corridors. Entrances to
+/// rooms get doors
+pub struct BspGenerator {
+    width: usize,
+    height: usize,
+    min_room_size: usize,
+    max_room_size: usize,
+    /// How many times the map is split at most
+    depth: u32,
+}
+
+impl BspGenerator {
+    pub fn new(width: usize, height: usize) -> BspGenerator {
+        BspGenerator {
+            width,
+            height,
+            min_room_size: DEFAULT_MIN_ROOM_SIZE,
+            max_room_size: DEFAULT_MAX_ROOM_SIZE,
+            depth: DEFAULT_DEPTH,
+        }
+    }
+
+    /// Sets bounds (inclusive) for both sides of the rooms
+    pub fn with_room_size(mut self, min: usize, max: usize) -> BspGenerator {
+        assert!(min > 0 && min <= max, "Invalid room size bounds!");
+        self.min_room_size = min;
+        self.max_room_size = max;
+        self
+    }
+
+    pub fn with_depth(mut self, depth: u32) -> BspGenerator {
+        self.depth = depth;
+        self
+    }
+
+    /// Smallest partition that can hold a room, rooms never touch partition
+    /// borders so the neighbouring rooms are always separated by walls
+    fn min_partition(&self) -> usize {
+        self.min_room_size + 2
+    }
+
+    /// Returns rooms carved in the partition
+    fn split(&self, map: &mut Map, rng: &mut Rng, partition: Rect, depth: u32) -> Vec<Rect> {
+        let min = self.min_partition();
+        let vertical = partition.width >= 2 * min;
+        let horizontal = partition.height >= 2 * min;
+        if depth == 0 || !(vertical || horizontal) {
+            return vec![self.carve_room(map, rng, partition)]
+        }
+
+        let split_vertically = match (vertical, horizontal) {
+            (true, true) if partition.width == partition.height => rng.chance(50),
+            (true, true) => partition.width > partition.height,
+            (vertical, _) => vertical,
+        };
+        let (first, second) = if split_vertically {
+            let width = rng.range(min, partition.width - min + 1);
+            (Rect { width, ..partition },
+             Rect { x: partition.x + width, width: partition.width - width, ..partition })
+        } else {
+            let height = rng.range(min, partition.height - min + 1);
+            (Rect { height, ..partition },
+             Rect { y: partition.y + height, height: partition.height - height, ..partition })
+        };
+
+        let mut rooms = self.split(map, rng, first, depth - 1);
+        let second = self.split(map, rng, second, depth - 1);
+        let from = *rng.choose(&rooms).unwrap();
+        let to = *rng.choose(&second).unwrap();
+        dig_tunnel(map, from.center(), to.center(), rng.chance(50));
+        rooms.extend(second);
+        rooms
+    }
+
+    fn carve_room(&self, map: &mut Map, rng: &mut Rng, partition: Rect) -> Rect {
+        let max_width = cmp::min(self.max_room_size, partition.width - 2);
+        let max_height = cmp::min(self.max_room_size, partition.height - 2);
+        let width = rng.range(self.min_room_size, max_width + 1);
+        let height = rng.range(self.min_room_size, max_height + 1);
+        let room = Rect {
+            x: rng.range(partition.x + 1, partition.x + partition.width - width),
+            y: rng.range(partition.y + 1, partition.y + partition.height - height),
+            width,
+            height,
+        };
+        for x in room.x..room.x + width {
+            for y in room.y..room.y + height {
+                map.0[x][y] = Tile::new(TileType::Ground);
+            }
+        }
+        room
+    }
+}
+
+/// Puts doors where corridors pass through the walls around rooms
+fn place_doors(map: &mut Map, rooms: &[Rect]) {
+    for room in rooms {
+        let ring = (room.x - 1..room.x + room.width + 1)
+            .flat_map(|x| vec![(x, room.y - 1), (x, room.y + room.height)])
+            .chain((room.y..room.y + room.height)
+                   .flat_map(|y| vec![(room.x - 1, y), (room.x + room.width, y)]));
+        for (x, y) in ring.collect::<Vec<_>>() {
+            if !matches!(map.get(x, y).tile_type, TileType::Ground) ||
+                rooms.iter().any(|other| other.contains(x, y)) {
+                continue
+            }
+            if is_doorway(map, x, y) {
+                map.0[x][y] = Tile::new(TileType::Door { closed: true, locked: false, lock: None });
+            }
+        }
+    }
+}
+
+impl MapGenerator for BspGenerator {
+    fn generate(&self, rng: &mut Rng) -> Map {
+        assert!(self.width >= self.min_partition() && self.height >= self.min_partition(),
+                "Map is too small for a single room!");
+        let mut map = Map::new(vec![vec![Tile::new(TileType::Wall); self.height]; self.width]);
+        let partition = Rect { x: 0, y: 0, width: self.width, height: self.height };
+        let rooms = self.split(&mut map, rng, partition, self.depth);
+        place_doors(&mut map, &rooms);
+        map
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map::generators::floor_regions;
+
+    #[test]
+    fn every_floor_tile_reachable() {
+        for seed in 0..30 {
+            let map = BspGenerator::new(60, 40).generate(&mut Rng::new(seed));
+            assert_eq!(floor_regions(&map).len(), 1, "Seed {} has unreachable floor", seed);
+        }
+    }
+
+    #[test]
+    fn rooms_get_doors() {
+        let doors = (0..10)
+            .map(|seed| BspGenerator::new(60, 40).generate(&mut Rng::new(seed)))
+            .map(|map| map.tiles().filter(|&(_, _, tile)| tile.can_be_opened()).count())
+            .sum::<usize>();
+        assert!(doors > 0);
+    }
+
+    #[test]
+    fn same_seed_same_map() {
+        let render = |seed| format!("{:?}", BspGenerator::new(40, 30).generate(&mut Rng::new(seed))
+                                    .tiles().map(|(_, _, tile)| tile.tile_type.clone())
+                                    .collect::<Vec<_>>());
+        assert_eq!(render(3), render(3));
+        assert_ne!(render(3), render(4));
+    }
+
+    #[test]
+    fn single_room_without_splits() {
+        let map = BspGenerator::new(30, 30)
+            .with_depth(0)
+            .with_room_size(5, 7)
+            .generate(&mut Rng::new(1));
+        let floor = floor_regions(&map).concat().len();
+        assert!((25..50).contains(&floor));
+        assert!(!map.tiles().any(|(_, _, tile)| tile.can_be_opened()));
+    }
+}
@@ -0,0 +1,172 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::cmp;
+
+use rng::Rng;
+use map::tiles::*;
+use super::{MapGenerator, Stage, ConnectRegions, floor_regions};
+
+const DEFAULT_FILL_PERCENT: u32 = 45;
+const DEFAULT_ITERATIONS: u32 = 4;
+const DEFAULT_BIRTH: usize = 5;
+const DEFAULT_SURVIVAL: usize = 4;
+
+/// Fills the map with random walls and smooths it with cellular automaton,
+/// disconnected caves are then joined with tunnels
+pub struct CaveGenerator {
+    width: usize,
+    height: usize,
+    /// Percent of tiles that start as walls
+    fill_percent: u32,
+    /// Number of smoothing steps
+    iterations: u32,
+    /// Floor becomes wall if it has at least this many walls around
+    birth: usize,
+    /// Wall stays wall if it has at least this many walls around
+    survival: usize,
+}
+
+impl CaveGenerator {
+    pub fn new(width: usize, height: usize) -> CaveGenerator {
+        assert!(width >= 3 && height >= 3, "Cave must have some space inside!");
+        CaveGenerator {
+            width,
+            height,
+            fill_percent: DEFAULT_FILL_PERCENT,
+            iterations: DEFAULT_ITERATIONS,
+            birth: DEFAULT_BIRTH,
+            survival: DEFAULT_SURVIVAL,
+        }
+    }
+
+    pub fn with_fill_percent(mut self, fill_percent: u32) -> CaveGenerator {
+        self.fill_percent = fill_percent;
+        self
+    }
+
+    pub fn with_iterations(mut self, iterations: u32) -> CaveGenerator {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Sets neighbouring walls needed for floor to turn into wall (birth) and
+    /// for wall to remain (survival)
+    pub fn with_thresholds(mut self, birth: usize, survival: usize) -> CaveGenerator {
+        self.birth = birth;
+        self.survival = survival;
+        self
+    }
+
+    fn is_border(&self, x: usize, y: usize) -> bool {
+        x == 0 || y == 0 || x == self.width - 1 || y == self.height - 1
+    }
+
+    /// Walls among 8 neighbours, the border counts as walls
+    fn walls_around(&self, walls: &[Vec<bool>], x: usize, y: usize) -> usize {
+        let columns = x.saturating_sub(1)..cmp::min(x + 2, self.width);
+        let rows = y.saturating_sub(1)..cmp::min(y + 2, self.height);
+        columns
+            .flat_map(|nx| rows.clone().map(move |ny| (nx, ny)))
+            .filter(|&(nx, ny)| (nx, ny) != (x, y) && walls[nx][ny])
+            .count()
+    }
+
+    fn smooth(&self, walls: &[Vec<bool>]) -> Vec<Vec<bool>> {
+        (0..self.width)
+            .map(|x| (0..self.height)
+                 .map(|y| {
+                     let around = self.walls_around(walls, x, y);
+                     self.is_border(x, y) ||
+                         if walls[x][y] { around >= self.survival } else { around >= self.birth }
+                 })
+                 .collect())
+            .collect()
+    }
+}
+
+impl MapGenerator for CaveGenerator {
+    fn generate(&self, rng: &mut Rng) -> Map {
+        let mut walls: Vec<Vec<bool>> = (0..self.width)
+            .map(|x| (0..self.height)
+                 .map(|y| self.is_border(x, y) || rng.chance(self.fill_percent))
+                 .collect())
+            .collect();
+        for _ in 0..self.iterations {
+            walls = self.smooth(&walls);
+        }
+        // cave can't be all rock
+        let center = (self.width / 2, self.height / 2);
+        if walls.iter().all(|column| column.iter().all(|&wall| wall)) {
+            walls[center.0][center.1] = false;
+        }
+
+        let mut map = Map::new(walls.iter()
+            .map(|column| column.iter()
+                 .map(|&wall| Tile::new(if wall { TileType::Wall } else { TileType::Ground }))
+                 .collect())
+            .collect());
+        ConnectRegions::new().apply(&mut map, rng);
+        if floor_regions(&map).is_empty() {
+            map.0[center.0][center.1] = Tile::new(TileType::Ground);
+        }
+        map
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caves_fully_reachable() {
+        for seed in 0..30 {
+            let map = CaveGenerator::new(60, 40).generate(&mut Rng::new(seed));
+            assert_eq!(floor_regions(&map).len(), 1, "Seed {} has unreachable floor", seed);
+        }
+    }
+
+    #[test]
+    fn border_is_solid() {
+        for seed in 0..10 {
+            let map = CaveGenerator::new(30, 20).with_fill_percent(30).generate(&mut Rng::new(seed));
+            assert!(map.tiles()
+                    .filter(|&(x, y, _)| x == 0 || y == 0 || x == 29 || y == 19)
+                    .all(|(_, _, tile)| !tile.is_passable()));
+        }
+    }
+
+    #[test]
+    fn parameters_shape_caves() {
+        let floor = |generator: CaveGenerator| floor_regions(&generator.generate(&mut Rng::new(5)))
+            .concat().len();
+        let sparse = floor(CaveGenerator::new(40, 30).with_fill_percent(30));
+        let dense = floor(CaveGenerator::new(40, 30).with_fill_percent(55));
+        assert!(sparse > dense);
+        let rocky = CaveGenerator::new(40, 30).with_fill_percent(100).with_iterations(0);
+        assert_eq!(floor(rocky), 1);
+        let open = CaveGenerator::new(40, 30).with_thresholds(9, 9).with_iterations(1);
+        assert_eq!(floor(open), 38 * 28);
+    }
+}
@@ -0,0 +1,115 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+
+mod bsp;
+mod caves;
+mod vaults;
+mod pipeline;
+
+use std::cmp;
+
+use rng::Rng;
+use super::tiles::*;
+use super::analysis::Regions;
+
+pub use self::bsp::BspGenerator;
+pub use self::caves::CaveGenerator;
+pub use self::vaults::{Vault, PlaceVaults};
+pub use self::pipeline::{Stage, Pipeline, ConnectRegions, CullDoors, PlaceStairs, Pillars};
+
+pub trait MapGenerator {
+    /// All randomness must come from the given generator, so the same seed
+    /// gives the same map
+    fn generate(&self, rng: &mut Rng) -> Map;
+}
+
+/// Always generate the same simple map. Just for testing and debugging
+pub struct SimpleBoxGenerator {
+    width: usize,
+    height: usize,
+}
+
+impl SimpleBoxGenerator {
+    pub fn new(width: usize, height: usize) -> SimpleBoxGenerator {
+        SimpleBoxGenerator { width, height }
+    }
+}
+
+impl MapGenerator for SimpleBoxGenerator {
+
+    fn generate(&self, _rng: &mut Rng) -> Map {
+        let width = self.width;
+        let height = self.height;
+
+        let mut map = vec![vec![Tile::new(TileType::Ground); height]; width];
+        for column in map.iter_mut() {
+            column[0] = Tile::new(TileType::Wall);
+            column[height - 1] = Tile::new(TileType::Wall);
+        }
+
+        for &x in &[0, width - 1] {
+            for tile in map[x].iter_mut() {
+                *tile = Tile::new(TileType::Wall);
+            }
+        }
+
+        Map::new(map)
+    }
+}
+
+/// Groups of walkable tiles, generators use it to make sure every part of
+/// the level is reachable. Locked rooms belong to the region of their door
+pub(crate) fn floor_regions(map: &Map) -> Vec<Vec<(usize, usize)>> {
+    let regions = Regions::through_locks(map);
+    (0..regions.count()).map(|label| regions.tiles(label).to_vec()).collect()
+}
+
+fn is_wall(map: &Map, x: usize, y: usize) -> bool {
+    matches!(map.get(x, y).tile_type, TileType::Wall)
+}
+
+/// Digs L-shaped tunnel through the rock between two points, going along
+/// x first or along y first
+pub(crate) fn dig_tunnel(map: &mut Map, from: (usize, usize), to: (usize, usize),
+                         horizontal_first: bool) {
+    let corner = if horizontal_first { (to.0, from.1) } else { (from.0, to.1) };
+    for &(start, end) in &[(from, corner), (corner, to)] {
+        for x in cmp::min(start.0, end.0)..cmp::max(start.0, end.0) + 1 {
+            for y in cmp::min(start.1, end.1)..cmp::max(start.1, end.1) + 1 {
+                if is_wall(map, x, y) {
+                    map.0[x][y] = Tile::new(TileType::Ground);
+                }
+            }
+        }
+    }
+}
+
+/// Tile stands between two walls, either horizontally or vertically, so
+/// a door fits there
+pub(crate) fn is_doorway(map: &Map, x: usize, y: usize) -> bool {
+    let Map(ref tiles) = *map;
+    x > 0 && y > 0 && x + 1 < tiles.len() && y + 1 < tiles[0].len() &&
+        ((is_wall(map, x - 1, y) && is_wall(map, x + 1, y)) ||
+         (is_wall(map, x, y - 1) && is_wall(map, x, y + 1)))
+}
@@ -0,0 +1,281 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::cmp;
+
+use rng::Rng;
+use map::tiles::*;
+use super::{MapGenerator, floor_regions, dig_tunnel, is_doorway};
+
+/// Disconnected regions smaller than this are filled instead of being joined
+const DEFAULT_MIN_REGION_SIZE: usize = 6;
+
+/// Step of the [`Pipeline`], changes the map in place
+pub trait Stage {
+    /// All randomness must come from the given generator, like in
+    /// [`MapGenerator::generate`]
+    fn apply(&self, map: &mut Map, rng: &mut Rng);
+}
+
+/// Takes the map of the base generator and passes it through the stages in
+/// order, so layouts and finishing steps can be combined freely
+pub struct Pipeline {
+    base: Box<dyn MapGenerator>,
+    stages: Vec<Box<dyn Stage>>,
+}
+
+impl Pipeline {
+    pub fn new(base: Box<dyn MapGenerator>) -> Pipeline {
+        Pipeline { base, stages: vec![] }
+    }
+
+    /// Adds the stage to the end of the pipeline
+    pub fn then(mut self, stage: Box<dyn Stage>) -> Pipeline {
+        self.stages.push(stage);
+        self
+    }
+}
+
+impl MapGenerator for Pipeline {
+    fn generate(&self, rng: &mut Rng) -> Map {
+        let mut map = self.base.generate(rng);
+        for stage in self.stages.iter() {
+            stage.apply(&mut map, rng);
+        }
+        map
+    }
+}
+
+/// Joins disconnected parts of the level to the largest one with tunnels,
+/// tiny ones are filled with rock unless they hold stairs
+pub struct ConnectRegions {
+    min_region_size: usize,
+}
+
+impl ConnectRegions {
+    pub fn new() -> ConnectRegions {
+        ConnectRegions { min_region_size: DEFAULT_MIN_REGION_SIZE }
+    }
+
+    pub fn with_min_region_size(mut self, min_region_size: usize) -> ConnectRegions {
+        self.min_region_size = min_region_size;
+        self
+    }
+}
+
+impl Default for ConnectRegions {
+    fn default() -> ConnectRegions {
+        ConnectRegions::new()
+    }
+}
+
+impl Stage for ConnectRegions {
+    fn apply(&self, map: &mut Map, _rng: &mut Rng) {
+        let mut regions = floor_regions(map);
+        regions.sort_by_key(|region| cmp::Reverse(region.len()));
+        let mut connected = match regions.first() {
+            Some(main) => main.clone(),
+            None => return,
+        };
+        for region in regions.into_iter().skip(1) {
+            let has_stairs = region.iter()
+                .any(|&(x, y)| matches!(map.get(x, y).tile_type, TileType::Stairs { .. }));
+            if region.len() < self.min_region_size && !has_stairs {
+                for &(x, y) in region.iter() {
+                    map.0[x][y] = Tile::new(TileType::Wall);
+                }
+                continue
+            }
+            let distance = |a: (usize, usize), b: (usize, usize)|
+                (a.0 as isize - b.0 as isize).abs() + (a.1 as isize - b.1 as isize).abs();
+            let (from, to) = region.iter()
+                .flat_map(|&from| connected.iter().map(move |&to| (from, to)))
+                .min_by_key(|&(from, to)| distance(from, to))
+                .unwrap();
+            dig_tunnel(map, from, to, true);
+            connected.extend(region);
+        }
+    }
+}
+
+/// Removes doors that don't stand between two walls, and randomly some of
+/// the rest. Doors with locks are always kept
+pub struct CullDoors {
+    /// Chance of a proper door without lock to stay
+    keep_percent: u32,
+}
+
+impl CullDoors {
+    pub fn new(keep_percent: u32) -> CullDoors {
+        CullDoors { keep_percent }
+    }
+}
+
+impl Stage for CullDoors {
+    fn apply(&self, map: &mut Map, rng: &mut Rng) {
+        let doors: Vec<_> = map.tiles()
+            .filter(|&(_, _, tile)| matches!(tile.tile_type, TileType::Door { lock: None, .. }))
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        for (x, y) in doors {
+            if !is_doorway(map, x, y) || !rng.chance(self.keep_percent) {
+                map.0[x][y] = Tile::new(TileType::Ground);
+            }
+        }
+    }
+}
+
+/// Puts stairs down on random ground tiles, they are linked to the next
+/// level when followed for the first time
+pub struct PlaceStairs {
+    count: usize,
+}
+
+impl PlaceStairs {
+    pub fn new(count: usize) -> PlaceStairs {
+        PlaceStairs { count }
+    }
+}
+
+impl Stage for PlaceStairs {
+    fn apply(&self, map: &mut Map, rng: &mut Rng) {
+        let mut ground: Vec<_> = map.tiles()
+            .filter(|&(_, _, tile)| matches!(tile.tile_type, TileType::Ground))
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        rng.shuffle(&mut ground);
+        for &(x, y) in ground.iter().take(self.count) {
+            map.0[x][y] = Tile::new(TileType::Stairs { down: true });
+        }
+    }
+}
+
+/// Decorates open areas with pillars. Pillar is put only where all eight
+/// neighbours are ground, so it never cuts any path
+pub struct Pillars {
+    percent: u32,
+}
+
+impl Pillars {
+    /// Chance for every suitable tile to become a pillar
+    pub fn new(percent: u32) -> Pillars {
+        Pillars { percent }
+    }
+}
+
+impl Stage for Pillars {
+    fn apply(&self, map: &mut Map, rng: &mut Rng) {
+        let Map(ref tiles) = *map;
+        let (width, height) = (tiles.len(), tiles[0].len());
+        for x in 1..width.saturating_sub(1) {
+            for y in 1..height.saturating_sub(1) {
+                let open = (x - 1..x + 2)
+                    .all(|nx| (y - 1..y + 2)
+                         .all(|ny| matches!(map.get(nx, ny).tile_type, TileType::Ground)));
+                if open && rng.chance(self.percent) {
+                    map.0[x][y] = Tile::new(TileType::Wall);
+                }
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map::generators::{BspGenerator, CaveGenerator, SimpleBoxGenerator};
+    use map::generators::{Vault, PlaceVaults};
+
+    fn count(map: &Map, predicate: fn(&TileType) -> bool) -> usize {
+        map.tiles().filter(|&(_, _, tile)| predicate(&tile.tile_type)).count()
+    }
+
+    #[test]
+    fn combined_pipeline() {
+        let vault = Vault::parse("#####\n#.>.#\n##+##").unwrap();
+        let pipeline = Pipeline::new(Box::new(CaveGenerator::new(50, 40)))
+            .then(Box::new(Pillars::new(20)))
+            .then(Box::new(PlaceVaults::new(vec![vault])))
+            .then(Box::new(PlaceStairs::new(2)))
+            .then(Box::new(ConnectRegions::new()));
+        for seed in 0..10 {
+            let map = pipeline.generate(&mut Rng::new(seed));
+            assert_eq!(floor_regions(&map).len(), 1, "Seed {} has unreachable floor", seed);
+            assert!(count(&map, |tile| matches!(*tile, TileType::Stairs { down: true })) >= 2);
+        }
+    }
+
+    #[test]
+    fn culling_doors() {
+        let mut map = Map::from_ascii("
+            #######
+            #..+..#
+            ###+###
+            #..1..#
+            #######").unwrap();
+        CullDoors::new(100).apply(&mut map, &mut Rng::new(0));
+        assert_eq!(map.to_ascii().lines().nth(1), Some("#.....#"));
+        assert_eq!(map.to_ascii().lines().nth(2), Some("###+###"));
+
+        let is_door = |tile: &TileType| matches!(*tile, TileType::Door { .. });
+        let mut map = BspGenerator::new(60, 40).generate(&mut Rng::new(0));
+        CullDoors::new(0).apply(&mut map, &mut Rng::new(0));
+        assert_eq!(count(&map, is_door), 0);
+    }
+
+    #[test]
+    fn pillars_keep_paths() {
+        let pipeline = Pipeline::new(Box::new(SimpleBoxGenerator::new(20, 20)))
+            .then(Box::new(Pillars::new(100)));
+        let map = pipeline.generate(&mut Rng::new(0));
+        assert!(count(&map, |tile| matches!(*tile, TileType::Wall)) > 4 * 19);
+        assert_eq!(floor_regions(&map).len(), 1);
+    }
+
+    #[test]
+    fn tunnels_join_regions() {
+        let mut map = Map::from_ascii("
+            ##########
+            #...##...#
+            #...##...#
+            #######.>#
+            ##########").unwrap();
+        ConnectRegions::new().apply(&mut map, &mut Rng::new(0));
+        assert_eq!(floor_regions(&map).len(), 1);
+        assert_eq!(count(&map, |tile| matches!(*tile, TileType::Stairs { .. })), 1);
+    }
+
+    #[test]
+    fn locked_rooms_stay_locked() {
+        let mut map = Map::from_ascii("
+            ##########
+            #....#...#
+            #....1.>.#
+            #....#...#
+            ##########").unwrap();
+        let before = map.to_ascii();
+        ConnectRegions::new().apply(&mut map, &mut Rng::new(0));
+        assert_eq!(map.to_ascii(), before);
+    }
+}
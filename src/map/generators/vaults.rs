@@ -0,0 +1,232 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use rng::Rng;
+use map::tiles::*;
+use map::ascii::{self, AsciiError};
+use map::analysis::Regions;
+use super::Stage;
+
+const DEFAULT_ATTEMPTS: u32 = 50;
+
+/// Hand-designed piece of the level. Spaces in the template are transparent
+/// and keep whatever the base generator has put there
+#[derive(Clone, Debug)]
+pub struct Vault {
+    /// Indexed by x, then y like tiles of [`Map`]
+    cells: Vec<Vec<Option<TileType>>>,
+}
+
+impl Vault {
+    /// Parses template drawn with the symbols of [`Map::from_ascii`], lines
+    /// are taken as is and shorter ones are padded with spaces
+    pub fn parse(text: &str) -> Result<Vault, AsciiError> {
+        let lines: Vec<&str> = text.lines().collect();
+        let first = lines.iter().position(|line| !line.trim().is_empty());
+        let last = lines.iter().rposition(|line| !line.trim().is_empty());
+        let lines = match (first, last) {
+            (Some(first), Some(last)) => &lines[first..last + 1],
+            _ => return Err(AsciiError::Empty),
+        };
+        let width = lines.iter().map(|line| line.trim_end().chars().count()).max().unwrap();
+        let mut cells = vec![vec![None; lines.len()]; width];
+        for (y, line) in lines.iter().enumerate() {
+            for (x, symbol) in line.trim_end().chars().enumerate() {
+                if symbol != ' ' {
+                    cells[x][y] = Some(ascii::tile_type(symbol)
+                                       .ok_or(AsciiError::UnknownSymbol { symbol, x, y })?);
+                }
+            }
+        }
+        Ok(Vault { cells })
+    }
+
+    pub fn width(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells[0].len()
+    }
+
+    /// Turned 90 degrees clockwise
+    pub fn rotated(&self) -> Vault {
+        let height = self.height();
+        let cells = (0..height)
+            .map(|x| (0..self.width()).map(|y| self.cells[y][height - 1 - x].clone()).collect())
+            .collect();
+        Vault { cells }
+    }
+
+    /// Flipped left to right
+    pub fn mirrored(&self) -> Vault {
+        Vault { cells: self.cells.iter().rev().cloned().collect() }
+    }
+
+    /// All eight ways to rotate and mirror the vault
+    fn orientations(&self) -> Vec<Vault> {
+        let mut orientations = vec![self.clone()];
+        for _ in 0..3 {
+            let next = orientations.last().unwrap().rotated();
+            orientations.push(next);
+        }
+        let mirrored: Vec<_> = orientations.iter().map(Vault::mirrored).collect();
+        orientations.extend(mirrored);
+        orientations
+    }
+
+    fn stamp(&self, map: &mut Map, left: usize, top: usize) {
+        for (x, column) in self.cells.iter().enumerate() {
+            for (y, cell) in column.iter().enumerate() {
+                if let Some(ref tile_type) = *cell {
+                    map.0[left + x][top + y] = Tile::new(tile_type.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Stamps vaults into the map, randomly rotated and mirrored. Vaults never
+/// overlap each other, never touch the map border and never cut off any
+/// part of the level; vault that can't be placed is left out
+pub struct PlaceVaults {
+    vaults: Vec<Vault>,
+    /// Random spots tried for every vault
+    attempts: u32,
+}
+
+impl PlaceVaults {
+    pub fn new(vaults: Vec<Vault>) -> PlaceVaults {
+        PlaceVaults { vaults, attempts: DEFAULT_ATTEMPTS }
+    }
+
+    pub fn with_attempts(mut self, attempts: u32) -> PlaceVaults {
+        self.attempts = attempts;
+        self
+    }
+
+    /// Returns true if the vault was placed
+    fn place(&self, map: &mut Map, occupied: &mut [Vec<bool>], rng: &mut Rng, vault: &Vault) -> bool {
+        let orientations = vault.orientations();
+        let (width, height) = (occupied.len(), occupied[0].len());
+        let regions = Regions::through_locks(map).count();
+        for _ in 0..self.attempts {
+            let vault = rng.choose(&orientations).unwrap();
+            if vault.width() + 2 > width || vault.height() + 2 > height {
+                continue
+            }
+            let left = rng.range(1, width - vault.width());
+            let top = rng.range(1, height - vault.height());
+            let overlaps = occupied[left..left + vault.width()].iter()
+                .any(|column| column[top..top + vault.height()].iter().any(|&taken| taken));
+            if overlaps {
+                continue
+            }
+            let mut stamped = map.clone();
+            vault.stamp(&mut stamped, left, top);
+            if Regions::through_locks(&stamped).count() > regions {
+                continue
+            }
+            *map = stamped;
+            for column in occupied[left..left + vault.width()].iter_mut() {
+                for taken in column[top..top + vault.height()].iter_mut() {
+                    *taken = true;
+                }
+            }
+            return true
+        }
+        false
+    }
+}
+
+impl Stage for PlaceVaults {
+    fn apply(&self, map: &mut Map, rng: &mut Rng) {
+        let mut occupied = {
+            let Map(ref tiles) = *map;
+            vec![vec![false; tiles[0].len()]; tiles.len()]
+        };
+        for vault in self.vaults.iter() {
+            self.place(map, &mut occupied, rng, vault);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map::generators::{MapGenerator, Pipeline, SimpleBoxGenerator};
+
+    fn shrine() -> Vault {
+        Vault::parse("#####\n#...#\n#.>.#\n##+##").unwrap()
+    }
+
+    #[test]
+    fn rotation_and_mirroring() {
+        let vault = Vault::parse("#+\n..\n. ").unwrap();
+        assert_eq!((vault.width(), vault.height()), (2, 3));
+        let rotated = vault.rotated();
+        assert_eq!((rotated.width(), rotated.height()), (3, 2));
+        assert!(matches!(rotated.cells[2][0], Some(TileType::Wall)));
+        assert!(rotated.cells[0][1].is_none());
+        assert!(matches!(vault.mirrored().cells[0][0], Some(TileType::Door { .. })));
+        let back = rotated.rotated().rotated().rotated();
+        assert_eq!(format!("{:?}", back), format!("{:?}", vault));
+    }
+
+    #[test]
+    fn vaults_are_stamped() {
+        let generator = Pipeline::new(Box::new(SimpleBoxGenerator::new(20, 20)))
+            .then(Box::new(PlaceVaults::new(vec![shrine(), shrine()])));
+        for seed in 0..10 {
+            let map = generator.generate(&mut Rng::new(seed));
+            let stairs = map.tiles()
+                .filter(|&(_, _, tile)| matches!(tile.tile_type, TileType::Stairs { .. }))
+                .count();
+            assert_eq!(stairs, 2);
+            assert_eq!(Regions::new(&map).count(), 1);
+        }
+    }
+
+    #[test]
+    fn locked_vaults() {
+        let treasury = Vault::parse("#####\n#.>.#\n##1##").unwrap();
+        let generator = Pipeline::new(Box::new(SimpleBoxGenerator::new(20, 20)))
+            .then(Box::new(PlaceVaults::new(vec![treasury])));
+        let map = generator.generate(&mut Rng::new(0));
+        assert!(map.tiles().any(|(_, _, tile)| matches!(tile.tile_type,
+                                                        TileType::Door { lock: Some(1), .. })));
+        assert_eq!(Regions::new(&map).count(), 2);
+        assert_eq!(Regions::through_locks(&map).count(), 1);
+    }
+
+    #[test]
+    fn vaults_dont_overlap() {
+        let big = Vault::parse("#######\n#.....#\n#.....#\n#.....#\n#.....#\n#.....#\n###+###").unwrap();
+        let generator = Pipeline::new(Box::new(SimpleBoxGenerator::new(12, 12)))
+            .then(Box::new(PlaceVaults::new(vec![big.clone(), big])));
+        let map = generator.generate(&mut Rng::new(0));
+        let doors = map.tiles().filter(|&(_, _, tile)| tile.can_be_opened()).count();
+        assert_eq!(doors, 1);
+    }
+}
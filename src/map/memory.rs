@@ -0,0 +1,126 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::collections::HashMap;
+
+use utils::*;
+use super::tiles::*;
+use super::fov::FieldOfView;
+
+/// What creature knows about a tile it has seen
+#[derive(Clone, Debug)]
+pub struct RememberedTile {
+    /// Type of the tile when it was seen last time
+    pub tile_type: TileType,
+    /// Whether some creature stood there at the moment
+    pub had_creature: bool,
+}
+
+/// Explored tiles of a single level
+#[derive(Debug)]
+pub struct LevelMemory {
+    tiles: Vec<Vec<Option<RememberedTile>>>,
+}
+
+/// Explored tiles of all the levels creature has visited. It is refreshed
+/// when the creature moves and when tiles of its level change, changes
+/// creature doesn't see are not noticed
+#[derive(Debug, Default)]
+pub struct MapMemory {
+    levels: HashMap<usize, LevelMemory>,
+}
+
+impl LevelMemory {
+    fn new(width: usize, height: usize) -> LevelMemory {
+        LevelMemory { tiles: vec![vec![None; height]; width] }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&RememberedTile> {
+        self.tiles.get(x)
+            .and_then(|column| column.get(y))
+            .and_then(|tile| tile.as_ref())
+    }
+
+    /// All explored tiles with their coordinates
+    pub fn explored(&self) -> Vec<(usize, usize, &RememberedTile)> {
+        self.tiles.iter()
+            .enumerate()
+            .flat_map(|(x, column)| column.iter()
+                      .enumerate()
+                      .filter_map(move |(y, tile)| tile.as_ref().map(|tile| (x, y, tile))))
+            .collect()
+    }
+}
+
+impl MapMemory {
+    pub fn new() -> MapMemory {
+        MapMemory::default()
+    }
+
+    pub fn level(&self, level: usize) -> Option<&LevelMemory> {
+        self.levels.get(&level)
+    }
+
+    pub fn get(&self, position: Position) -> Option<&RememberedTile> {
+        self.level(position.level).and_then(|level| level.get(position.x, position.y))
+    }
+
+    /// Remembers everything currently visible
+    pub(crate) fn update(&mut self, map: &Map, fov: &FieldOfView) {
+        let Map(ref tiles) = *map;
+        let level = self.levels.entry(fov.origin().level)
+            .or_insert_with(|| LevelMemory::new(tiles.len(), tiles[0].len()));
+        for position in fov.visible_positions() {
+            let tile = map.get_by_pos(position);
+            level.tiles[position.x][position.y] = Some(RememberedTile {
+                tile_type: tile.tile_type.clone(),
+                had_creature: tile.creature.as_ref()
+                    .is_some_and(|creature| creature.upgrade().is_some()),
+            });
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map::generators::*;
+    use rng::Rng;
+
+    #[test]
+    fn remembers_seen_tiles() {
+        let mut map = SimpleBoxGenerator::new(20, 20).generate(&mut Rng::new(0));
+        map.0[4][4] = Tile::new(TileType::Door { closed: true, locked: false, lock: None });
+        let mut memory = MapMemory::new();
+        let origin = Position { level: 0, x: 2, y: 2 };
+        memory.update(&map, &FieldOfView::new(&map, origin, 3));
+
+        match memory.get(Position { x: 4, y: 4, ..origin }) {
+            Some(&RememberedTile { tile_type: TileType::Door { closed: true, .. }, .. }) => (),
+            _ => panic!("Door must be remembered"),
+        }
+        assert!(memory.get(Position { x: 10, y: 10, ..origin }).is_none());
+        assert!(memory.level(1).is_none());
+    }
+}
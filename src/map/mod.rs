@@ -23,4 +23,10 @@
     */
 
 pub mod tiles;
-pub mod generators;
\ No newline at end of file
+pub mod generators;
+pub mod fov;
+pub mod memory;
+pub mod pathfinding;
+pub mod sound;
+pub mod ascii;
+pub mod analysis;
\ No newline at end of file
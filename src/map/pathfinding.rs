@@ -0,0 +1,320 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::rc::Rc;
+
+use utils::*;
+use super::tiles::*;
+
+/// What Dijkstra map leads to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Goal {
+    MainCharacter,
+    /// All the stairs of the level
+    Exits,
+}
+
+/// Distances (in time-points of the steps) from every tile of a level to the nearest goal. AI can
+/// descend it to reach goals or descend the inverted one to flee from them
+#[derive(Debug)]
+pub struct DijkstraMap {
+    level: usize,
+    /// Whether steps past wall corners were allowed when it was built
+    corner_cutting: bool,
+    distances: Vec<Vec<Option<i32>>>,
+}
+
+struct CachedMap {
+    goals: Vec<Position>,
+    map: Rc<DijkstraMap>,
+}
+
+/// Dijkstra maps computed for levels, reused while goals stay where they were
+#[derive(Default)]
+pub(crate) struct PathCache {
+    maps: HashMap<(usize, Goal), CachedMap>,
+}
+
+/// Neighbour positions inside the map with directions leading to them,
+/// diagonal steps past wall corners are left out unless corner cutting is allowed
+pub(crate) fn neighbours(map: &Map, position: Position, corner_cutting: bool)
+                         -> Vec<(Direction, Position)> {
+    let Map(ref tiles) = *map;
+    Direction::all().iter()
+        .filter(|&&direction| corner_cutting || !map.cuts_corner(position, direction))
+        .filter_map(|&direction| (position + direction).map(|next| (direction, next)))
+        .filter(|&(_, next)| next.x < tiles.len() && next.y < tiles[0].len())
+        .collect()
+}
+
+/// Estimation of the path cost for A*, exact on the empty map
+fn octile_distance(from: Position, to: Position) -> i32 {
+    let dx = (from.x as i32 - to.x as i32).abs();
+    let dy = (from.y as i32 - to.y as i32).abs();
+    let (straight, diagonal) = (Direction::Up.step_cost() as i32,
+                                Direction::UpLeft.step_cost() as i32);
+    straight * (dx - dy).abs() + diagonal * dx.min(dy)
+}
+
+/// Closed doors don't stop anyone, walking into them opens them
+pub(crate) fn is_walkable(tile: &Tile) -> bool {
+    tile.is_passable() || tile.can_be_opened()
+}
+
+fn is_free(tile: &Tile, avoid_creatures: bool) -> bool {
+    is_walkable(tile) &&
+        !(avoid_creatures && tile.creature.as_ref().is_some_and(|creature| creature.upgrade().is_some()))
+}
+
+/// Finds the shortest path with A*, returning steps to take. Creatures can
+/// optionally be treated as obstacles, except the one standing at the end
+pub fn find_path(map: &Map, from: Position, to: Position, avoid_creatures: bool,
+                 corner_cutting: bool) -> Option<Vec<Direction>> {
+    let Map(ref tiles) = *map;
+    if from.level != to.level || from.x >= tiles.len() || from.y >= tiles[0].len() ||
+        to.x >= tiles.len() || to.y >= tiles[0].len() || !is_walkable(map.get_by_pos(to)) {
+        return None
+    }
+
+    let mut came_from: Vec<Vec<Option<(Position, Direction)>>> =
+        vec![vec![None; tiles[0].len()]; tiles.len()];
+    let mut costs = vec![vec![None; tiles[0].len()]; tiles.len()];
+    let mut open = BinaryHeap::new();
+    costs[from.x][from.y] = Some(0);
+    open.push(Reverse((octile_distance(from, to), 0, from.x, from.y)));
+
+    while let Some(Reverse((_, cost, x, y))) = open.pop() {
+        let position = Position { level: from.level, x, y };
+        if position == to {
+            let mut path = vec![];
+            let mut current = to;
+            while let Some((previous, direction)) = came_from[current.x][current.y] {
+                path.push(direction);
+                current = previous;
+            }
+            path.reverse();
+            return Some(path)
+        }
+        if costs[x][y].is_some_and(|known| known < cost) {
+            continue
+        }
+        for (direction, next) in neighbours(map, position, corner_cutting) {
+            if next != to && !is_free(map.get_by_pos(next), avoid_creatures) {
+                continue
+            }
+            let next_cost = cost + direction.step_cost() as i32;
+            if costs[next.x][next.y].map_or(true, |known| next_cost < known) {
+                costs[next.x][next.y] = Some(next_cost);
+                came_from[next.x][next.y] = Some((position, direction));
+                open.push(Reverse((next_cost + octile_distance(next, to),
+                                   next_cost, next.x, next.y)));
+            }
+        }
+    }
+    None
+}
+
+impl DijkstraMap {
+    /// Builds map for several goals, creatures are not taken into account
+    pub fn new(map: &Map, level: usize, goals: &[Position], corner_cutting: bool) -> DijkstraMap {
+        let Map(ref tiles) = *map;
+        let mut distances = vec![vec![None; tiles[0].len()]; tiles.len()];
+        for goal in goals.iter().filter(|goal| goal.x < tiles.len() && goal.y < tiles[0].len()) {
+            distances[goal.x][goal.y] = Some(0);
+        }
+        DijkstraMap::relax(map, level, corner_cutting, distances)
+    }
+
+    /// Dijkstra's algorithm started from all the tiles that already have
+    /// distance assigned
+    fn relax(map: &Map, level: usize, corner_cutting: bool, mut distances: Vec<Vec<Option<i32>>>)
+             -> DijkstraMap {
+        let mut open = BinaryHeap::new();
+        for (x, column) in distances.iter().enumerate() {
+            for (y, distance) in column.iter().enumerate() {
+                if let Some(distance) = *distance {
+                    open.push(Reverse((distance, x, y)));
+                }
+            }
+        }
+        while let Some(Reverse((distance, x, y))) = open.pop() {
+            if distances[x][y].is_some_and(|known| known < distance) {
+                continue
+            }
+            for (direction, next) in neighbours(map, Position { level, x, y }, corner_cutting) {
+                if !is_walkable(map.get_by_pos(next)) {
+                    continue
+                }
+                let next_distance = distance + direction.step_cost() as i32;
+                if distances[next.x][next.y].map_or(true, |known| next_distance < known) {
+                    distances[next.x][next.y] = Some(next_distance);
+                    open.push(Reverse((next_distance, next.x, next.y)));
+                }
+            }
+        }
+        DijkstraMap { level, corner_cutting, distances }
+    }
+
+    pub fn distance(&self, position: Position) -> Option<i32> {
+        if position.level != self.level {
+            return None
+        }
+        self.distances.get(position.x)
+            .and_then(|column| column.get(position.y))
+            .and_then(|distance| *distance)
+    }
+
+    /// Map for fleeing from the goals. Distances are negated and scaled, so
+    /// descending it leads away from goals, but not into dead ends nearby
+    pub fn inverted(&self, map: &Map) -> DijkstraMap {
+        let distances = self.distances.iter()
+            .map(|column| column.iter()
+                 .map(|distance| distance.map(|distance| -distance * 6 / 5))
+                 .collect())
+            .collect();
+        DijkstraMap::relax(map, self.level, self.corner_cutting, distances)
+    }
+
+    /// Direction of the step to the neighbour with the lowest value,
+    /// if it is lower than the current one
+    pub fn descend(&self, map: &Map, position: Position, avoid_creatures: bool)
+                   -> Option<Direction> {
+        let current = self.distance(position)?;
+        neighbours(map, position, self.corner_cutting).into_iter()
+            .filter(|&(_, next)| is_free(map.get_by_pos(next), avoid_creatures))
+            .filter_map(|(direction, next)| self.distance(next).map(|distance| (direction, distance)))
+            .filter(|&(_, distance)| distance < current)
+            .min_by_key(|&(_, distance)| distance)
+            .map(|(direction, _)| direction)
+    }
+}
+
+impl PathCache {
+    /// Returns cached map if goals haven't moved and corner cutting rule
+    /// hasn't changed since it was built
+    pub(crate) fn get(&mut self, map: &Map, level: usize, goal: Goal, goals: Vec<Position>,
+                      corner_cutting: bool) -> Rc<DijkstraMap> {
+        if let Some(cached) = self.maps.get(&(level, goal)) {
+            if cached.goals == goals && cached.map.corner_cutting == corner_cutting {
+                return cached.map.clone()
+            }
+        }
+        let dijkstra_map = Rc::new(DijkstraMap::new(map, level, &goals, corner_cutting));
+        self.maps.insert((level, goal), CachedMap { goals, map: dijkstra_map.clone() });
+        dijkstra_map
+    }
+
+    /// Drops all maps of the level, must be called whenever its tiles change
+    pub(crate) fn invalidate(&mut self, level: usize) {
+        self.maps.retain(|&(cached_level, _), _| cached_level != level);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map::generators::*;
+    use rng::Rng;
+
+    fn at(x: usize, y: usize) -> Position {
+        Position { level: 0, x, y }
+    }
+
+    /// Box with a wall in the middle having a single gap at the bottom
+    fn walled_map() -> Map {
+        let mut map = SimpleBoxGenerator::new(10, 10).generate(&mut Rng::new(0));
+        for y in 1..7 {
+            map.0[5][y] = Tile::new(TileType::Wall);
+        }
+        map
+    }
+
+    fn follow(start: Position, path: &[Direction]) -> Position {
+        path.iter().fold(start, |position, &direction| (position + direction).unwrap())
+    }
+
+    #[test]
+    fn path_around_the_wall() {
+        let map = walled_map();
+        let path = find_path(&map, at(2, 2), at(8, 2), false, false).unwrap();
+        assert_eq!(follow(at(2, 2), &path), at(8, 2));
+        let through_gap = (0..path.len())
+            .map(|steps| follow(at(2, 2), &path[..steps]))
+            .any(|position| position.x == 5 && position.y >= 7);
+        assert!(through_gap);
+        assert!(path.iter().any(|direction| direction.is_diagonal()));
+    }
+
+    #[test]
+    fn no_path_into_the_wall() {
+        let map = walled_map();
+        assert!(find_path(&map, at(2, 2), at(5, 2), false, false).is_none());
+    }
+
+    #[test]
+    fn corner_cutting_shortens_path() {
+        let mut map = SimpleBoxGenerator::new(10, 10).generate(&mut Rng::new(0));
+        map.0[3][2] = Tile::new(TileType::Wall);
+        assert_eq!(find_path(&map, at(2, 2), at(3, 3), false, true), Some(vec![Direction::DownRight]));
+        assert_eq!(find_path(&map, at(2, 2), at(3, 3), false, false).map(|path| path.len()), Some(2));
+        let dijkstra = DijkstraMap::new(&map, 0, &[at(3, 3)], true);
+        assert_eq!(dijkstra.descend(&map, at(2, 2), false), Some(Direction::DownRight));
+    }
+
+    #[test]
+    fn dijkstra_map_multiple_goals() {
+        let map = walled_map();
+        let dijkstra = DijkstraMap::new(&map, 0, &[at(1, 1), at(8, 1)], false);
+        assert_eq!(dijkstra.distance(at(2, 2)), Some(141));
+        assert_eq!(dijkstra.distance(at(7, 3)), Some(241));
+        assert_eq!(dijkstra.distance(at(5, 3)), None);
+        assert_eq!(dijkstra.descend(&map, at(3, 1), false), Some(Direction::Left));
+    }
+
+    #[test]
+    fn inverted_map_leads_away() {
+        let map = SimpleBoxGenerator::new(10, 10).generate(&mut Rng::new(0));
+        let flee = DijkstraMap::new(&map, 0, &[at(2, 2)], false).inverted(&map);
+        let direction = flee.descend(&map, at(3, 2), false).unwrap();
+        assert!(at(2, 2).distance(&(at(3, 2) + direction).unwrap()) > 1);
+    }
+
+    #[test]
+    fn cache_rebuilds_on_goal_move() {
+        let map = SimpleBoxGenerator::new(10, 10).generate(&mut Rng::new(0));
+        let mut cache = PathCache::default();
+        let first = cache.get(&map, 0, Goal::MainCharacter, vec![at(2, 2)], false);
+        let second = cache.get(&map, 0, Goal::MainCharacter, vec![at(2, 2)], false);
+        assert!(Rc::ptr_eq(&first, &second));
+        let third = cache.get(&map, 0, Goal::MainCharacter, vec![at(3, 2)], false);
+        assert!(!Rc::ptr_eq(&first, &third));
+        cache.invalidate(0);
+        let fourth = cache.get(&map, 0, Goal::MainCharacter, vec![at(3, 2)], false);
+        assert!(!Rc::ptr_eq(&third, &fourth));
+        let fifth = cache.get(&map, 0, Goal::MainCharacter, vec![at(3, 2)], true);
+        assert!(!Rc::ptr_eq(&fourth, &fifth));
+    }
+}
@@ -0,0 +1,87 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::collections::VecDeque;
+
+use utils::*;
+use super::tiles::*;
+
+/// Tells if the sound made at the source reaches the listener. Sound
+/// travels `loudness` steps at most, going around walls and closed doors
+/// but never through them
+pub fn can_hear(map: &Map, source: Position, listener: Position, loudness: usize) -> bool {
+    let Map(ref tiles) = *map;
+    if source.level != listener.level || source.x >= tiles.len() || source.y >= tiles[0].len() {
+        return false
+    }
+    let mut steps = vec![vec![None; tiles[0].len()]; tiles.len()];
+    let mut queue = VecDeque::new();
+    steps[source.x][source.y] = Some(0);
+    queue.push_back(source);
+
+    while let Some(position) = queue.pop_front() {
+        if position == listener {
+            return true
+        }
+        let step = steps[position.x][position.y].unwrap();
+        if step == loudness {
+            continue
+        }
+        for &direction in Direction::all() {
+            match position + direction {
+                Some(next) if next.x < tiles.len() && next.y < tiles[0].len() &&
+                    steps[next.x][next.y].is_none() &&
+                    !map.get_by_pos(next).blocks_sound() => {
+                    steps[next.x][next.y] = Some(step + 1);
+                    queue.push_back(next);
+                },
+                _ => (),
+            }
+        }
+    }
+    false
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map::generators::*;
+    use rng::Rng;
+
+    #[test]
+    fn closed_door_blocks_sound() {
+        let mut map = SimpleBoxGenerator::new(10, 10).generate(&mut Rng::new(0));
+        for y in 1..9 {
+            map.0[5][y] = Tile::new(TileType::Wall);
+        }
+        map.0[5][4] = Tile::new(TileType::Door { closed: true, locked: false, lock: None });
+        let source = Position { level: 0, x: 3, y: 4 };
+        let listener = Position { level: 0, x: 7, y: 4 };
+        assert!(!can_hear(&map, source, listener, 10));
+
+        map.0[5][4] = Tile::new(TileType::Door { closed: false, locked: false, lock: None });
+        assert!(can_hear(&map, source, listener, 10));
+        assert!(!can_hear(&map, source, listener, 3));
+    }
+}
@@ -25,10 +25,15 @@
 use std::rc::{Weak};
 
 use world::CreatureRef;
+use items::Item;
 
 use utils::*;
 
+/// Identifies which key opens the lock
+pub type LockId = u32;
+
 /// Representing tiled map for game
+#[derive(Clone)]
 pub struct Map(pub Vec<Vec<Tile>>);
 
 impl Map {
@@ -47,8 +52,20 @@ impl Map {
         self.get(position.x, position.y)
     }
 
-    pub fn tiles(&self) -> Tiles {
-        Tiles { x: 0, y: 0, map: &self }
+    /// Diagonal step from the position squeezes past the wall corner
+    pub fn cuts_corner(&self, position: Position, direction: Direction) -> bool {
+        let Map(ref tiles) = *self;
+        let is_wall = |direction| match position + direction {
+            Some(next) if next.x < tiles.len() && next.y < tiles[0].len() =>
+                matches!(self.get_by_pos(next).tile_type, TileType::Wall),
+            _ => false,
+        };
+        direction.components()
+            .is_some_and(|(vertical, horizontal)| is_wall(vertical) || is_wall(horizontal))
+    }
+
+    pub fn tiles(&self) -> Tiles<'_> {
+        Tiles { x: 0, y: 0, map: self }
     }
 }
 
@@ -62,7 +79,7 @@ impl <'a> Iterator for Tiles<'a> {
     type Item = (usize, usize, &'a Tile);
 
     fn next(&mut self) -> Option<(usize, usize, &'a Tile)> {
-        let &Map(ref tiles) = self.map;
+        let Map(ref tiles) = *self.map;
 
         match (self.x, self.y) {
             (_, y) if y >= tiles[0].len()   => None,
@@ -83,28 +100,53 @@ impl <'a> Iterator for Tiles<'a> {
 pub struct Tile {
     pub tile_type: TileType,
     pub creature: Option<Weak<CreatureRef>>,
+    pub corpses: Vec<Corpse>,
+    /// Items lying on the tile, can be picked up
+    pub items: Vec<Item>,
+}
+
+/// Remains of the creature killed on the tile
+#[derive(Clone, Debug)]
+pub struct Corpse {
+    pub name: String,
 }
 
 impl Tile {
     pub fn new(tile_type: TileType) -> Tile {
-        return Tile { tile_type, creature: None }
+        Tile { tile_type, creature: None, corpses: vec![], items: vec![] }
     }
 
     pub fn is_passable(&self) -> bool {
         use TileType::*;
-        match self.tile_type {
-            Ground | Stairs | Door { closed: false } => true,
-            _ => false,
-        }
+        matches!(self.tile_type, Ground | Stairs { .. } | Door { closed: false, .. })
+    }
+
+    /// Closed door that is not locked, walking into it opens it
+    pub fn can_be_opened(&self) -> bool {
+        matches!(self.tile_type, TileType::Door { closed: true, locked: false, .. })
+    }
+
+    pub fn blocks_sight(&self) -> bool {
+        use TileType::*;
+        matches!(self.tile_type, Wall | Door { closed: true, .. })
+    }
+
+    pub fn blocks_sound(&self) -> bool {
+        use TileType::*;
+        matches!(self.tile_type, Wall | Door { closed: true, .. })
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub enum TileType {
     Ground,
     Wall,
-    Door { closed: bool },
-    Stairs
+    /// Door may have a lock, then the key for it can lock and unlock the
+    /// door. Locked door is always closed and can be opened only with the
+    /// key (or bashed, which breaks the lock)
+    Door { closed: bool, locked: bool, lock: Option<LockId> },
+    /// Leads to the next level if goes down, or to the previous one otherwise
+    Stairs { down: bool },
 }
 
 #[cfg(test)]
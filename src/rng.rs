@@ -0,0 +1,136 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::cell::RefCell;
+
+/// Seeded pseudo-random generator (SplitMix64), the same seed always gives
+/// the same sequence
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+const GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Scrambles bits of the value, used both for output and for seeding streams
+fn mix(value: u64) -> u64 {
+    let mut z = value;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GAMMA);
+        mix(self.state)
+    }
+
+    /// Uniform number in `[low, high)`, high must be greater than low
+    pub fn range(&mut self, low: usize, high: usize) -> usize {
+        assert!(low < high, "Range can't be empty!");
+        low + (self.next_u64() % (high - low) as u64) as usize
+    }
+
+    /// True with given probability in percents
+    pub fn chance(&mut self, percent: u32) -> bool {
+        self.range(0, 100) < percent as usize
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            Some(&items[self.range(0, items.len())])
+        }
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.range(0, i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+/// Separate streams of the [`World`], so adding one random roll doesn't
+/// shift the others
+pub(crate) struct RngStreams {
+    pub map: RefCell<Rng>,
+    pub combat: RefCell<Rng>,
+    pub ai: RefCell<Rng>,
+}
+
+impl RngStreams {
+    pub fn new(seed: u64) -> RngStreams {
+        let stream = |index: u64| RefCell::new(Rng::new(mix(seed ^ mix(index))));
+        RngStreams {
+            map: stream(1),
+            combat: stream(2),
+            ai: stream(3),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut first = Rng::new(42);
+        let mut second = Rng::new(42);
+        for _ in 0..100 {
+            assert_eq!(first.next_u64(), second.next_u64());
+        }
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+    }
+
+    #[test]
+    fn streams_are_independent() {
+        let quiet = RngStreams::new(7);
+        let busy = RngStreams::new(7);
+        for _ in 0..10 {
+            busy.combat.borrow_mut().next_u64();
+        }
+        assert_eq!(quiet.map.borrow_mut().next_u64(), busy.map.borrow_mut().next_u64());
+        assert_ne!(quiet.map.borrow_mut().next_u64(), quiet.ai.borrow_mut().next_u64());
+    }
+
+    #[test]
+    fn range_and_shuffle() {
+        let mut rng = Rng::new(0);
+        assert!((0..1000).map(|_| rng.range(3, 7)).all(|value| (3..7).contains(&value)));
+        let mut items: Vec<_> = (0..20).collect();
+        rng.shuffle(&mut items);
+        let mut sorted = items.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+        assert!(rng.choose::<u32>(&[]).is_none());
+    }
+}
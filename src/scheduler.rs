@@ -21,30 +21,49 @@
     OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
     SOFTWARE.
  */
-use std;
 use std::collections::BinaryHeap;
 use std::cmp::Ordering;
+use std::mem;
 use std::rc::Weak;
-use std::cell::RefCell;
 
 use world::*;
 use utils::*;
 use actions::Action;
+use events::{TimedEvent, TimedEventId};
 
-/// Entry with creature and next action to be commited
-struct ActionEntry {
-    cost: RefCell<i32>,
-    action: Action,
+/// Creature's action or timed event waiting in the queue
+pub(crate) enum Scheduled {
+    Action(Action),
+    Event(TimedEventId, TimedEvent),
+}
+
+/// Entry with something to be commited at the given time
+struct QueueEntry {
+    /// Scheduler time when the entry is applied
+    time: u64,
+    /// Order of posting, entries scheduled to the same time are applied
+    /// first come, first served
+    order: u64,
+    /// Time refunded by the action that couldn't be subtracted from its
+    /// own cost, it is carried over to the next action of the creature
+    bonus_time: u32,
+    scheduled: Scheduled,
 }
 
 struct UnassignedEntry {
     creature: Weak<CreatureRef>,
     bonus_time: u32,
+    /// Time the next action of the creature is postponed by
+    delay: u32,
 }
 
 pub(crate) struct Scheduler {
     unassigned: Vec<UnassignedEntry>,
-    queue: BinaryHeap<ActionEntry>,
+    queue: BinaryHeap<QueueEntry>,
+    /// Time-points passed since scheduler creation
+    time: u64,
+    /// Number of entries posted so far
+    posted: u64,
 }
 
 pub(crate) enum SchedulerError {
@@ -52,33 +71,33 @@ pub(crate) enum SchedulerError {
     QueueIsEmpty,
 }
 
-impl ActionEntry {
-    fn new(action: Action, cost: u32) -> ActionEntry {
-        ActionEntry {
-            cost: RefCell::new(cost as i32),
-            action: action,
+impl QueueEntry {
+    fn is_action_of(&self, creature: &Weak<CreatureRef>) -> bool {
+        match self.scheduled {
+            Scheduled::Action(ref action) => action.actor().identical(creature),
+            Scheduled::Event(..) => false,
         }
     }
 }
 
-
-impl Ord for ActionEntry {
-    fn cmp(&self, other: &ActionEntry) -> Ordering {
-        other.cost.borrow().cmp(&self.cost.borrow())
+impl Ord for QueueEntry {
+    /// Reversed to make [`BinaryHeap`] pop the earliest entry
+    fn cmp(&self, other: &QueueEntry) -> Ordering {
+        (other.time, other.order).cmp(&(self.time, self.order))
     }
 }
 
-impl PartialOrd for ActionEntry {
-    fn partial_cmp(&self, other: &ActionEntry) -> Option<Ordering> {
-        Some(other.cost.borrow().cmp(&self.cost.borrow()))
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &QueueEntry) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-impl Eq for ActionEntry {}
+impl Eq for QueueEntry {}
 
-impl PartialEq for ActionEntry {
-    fn eq(&self, other: &ActionEntry) -> bool {
-        *self.cost.borrow() == *other.cost.borrow()
+impl PartialEq for QueueEntry {
+    fn eq(&self, other: &QueueEntry) -> bool {
+        (self.time, self.order) == (other.time, other.order)
     }
 }
 
@@ -88,66 +107,156 @@ impl Scheduler {
         Scheduler {
             unassigned: vec![],
             queue: BinaryHeap::new(),
+            time: 0,
+            posted: 0,
         }
     }
 
-    /// Adds action to schedulers priority queue
+    pub(crate) fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// Registers creature that has no action assigned yet, so it will be
+    /// reported by [`Scheduler::peek_next`] until some action is posted for it
+    pub(crate) fn add_creature(&mut self, creature: Weak<CreatureRef>) {
+        self.unassigned.push(UnassignedEntry { creature, bonus_time: 0, delay: 0 });
+    }
+
+    /// Forgets the creature along with its pending action, e.g. when it dies
+    pub(crate) fn remove_creature(&mut self, creature: &Weak<CreatureRef>) {
+        self.unassigned.retain(|UnassignedEntry { creature: entry, .. }|
+                               !entry.identical(creature));
+        self.remove_entry(|entry| entry.is_action_of(creature));
+    }
+
+    /// Returns true if creature is waiting for an action to be posted
+    pub(crate) fn is_unassigned(&self, creature: &Weak<CreatureRef>) -> bool {
+        self.unassigned.iter()
+            .any(|UnassignedEntry { creature: entry, .. }| entry.identical(creature))
+    }
+
+    fn push(&mut self, delay: u64, bonus_time: u32, scheduled: Scheduled) {
+        self.queue.push(QueueEntry {
+            time: self.time + delay,
+            order: self.posted,
+            bonus_time,
+            scheduled,
+        });
+        self.posted += 1;
+    }
+
+    /// Takes the first entry matching the predicate out of the queue
+    fn remove_entry<P>(&mut self, predicate: P) -> Option<QueueEntry>
+        where P: Fn(&QueueEntry) -> bool {
+        let mut entries = mem::take(&mut self.queue).into_vec();
+        let removed = entries.iter().position(predicate).map(|index| entries.swap_remove(index));
+        self.queue = BinaryHeap::from(entries);
+        removed
+    }
+
+    /// Adds action to schedulers priority queue. Bonus time left by previous
+    /// actions of the creature is spent first, so the action may be applied
+    /// right away; negative cost refunds time for the next actions.
     pub(crate) fn post_action(&mut self, action: Action) {
-        debug_assert!(!self.queue.iter()
-                      .any(|&ActionEntry { action: ref entry, .. }|
-                           entry.actor().identical(action.actor())));
-        let mut cost = action.cost();
+        debug_assert!(!self.queue.iter().any(|entry| entry.is_action_of(action.actor())));
+        let mut cost = action.cost() as i64;
         if let Some(index) = self.unassigned.iter()
-            .position(|&UnassignedEntry { ref creature, .. }|
+            .position(|UnassignedEntry { creature, .. }|
                           action.actor().identical(creature)) {
                 let entry = self.unassigned.swap_remove(index);
-                cost -= entry.bonus_time;
+                cost += entry.delay as i64 - entry.bonus_time as i64;
             }
 
-        self.queue.push(ActionEntry::new(action, cost));
+        let (delay, bonus_time) = if cost >= 0 {
+            (cost as u64, 0)
+        } else {
+            (0, (-cost) as u32)
+        };
+        self.push(delay, bonus_time, Scheduled::Action(action));
+    }
+
+    /// Schedules the event to happen after the delay
+    pub(crate) fn schedule_event(&mut self, delay: u32, event: TimedEvent) -> TimedEventId {
+        let id = TimedEventId(self.posted);
+        self.push(delay as u64, 0, Scheduled::Event(id, event));
+        id
+    }
+
+    /// Returns the event back if it hasn't happened yet
+    pub(crate) fn cancel_event(&mut self, id: TimedEventId) -> Option<TimedEvent> {
+        let entry = self.remove_entry(|entry| match entry.scheduled {
+            Scheduled::Event(other, _) => other == id,
+            Scheduled::Action(_) => false,
+        })?;
+        match entry.scheduled {
+            Scheduled::Event(_, event) => Some(event),
+            Scheduled::Action(_) => unreachable!(),
+        }
     }
 
-    pub(crate) fn peek_next(&mut self) -> Result<&Action, SchedulerError> {
-        while self.unassigned.last()
-            .map(|&UnassignedEntry { ref creature, .. }| creature.upgrade().is_none())
-            .unwrap_or(false) {
-                self.unassigned.pop();
+    /// Postpones pending action of the creature, or the next one it posts if
+    /// it is still deciding. Returns false if the creature isn't scheduled
+    pub(crate) fn delay_action(&mut self, creature: &Weak<CreatureRef>, delay: u32) -> bool {
+        if let Some(entry) = self.unassigned.iter_mut()
+            .find(|UnassignedEntry { creature: entry, .. }| entry.identical(creature)) {
+                entry.delay += delay;
+                return true
             }
+        match self.remove_entry(|entry| entry.is_action_of(creature)) {
+            Some(mut entry) => {
+                entry.time += delay as u64;
+                self.queue.push(entry);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Takes pending action of the creature back, so it has to decide again
+    pub(crate) fn cancel_action(&mut self, creature: &Weak<CreatureRef>) -> Option<Action> {
+        let entry = self.remove_entry(|entry| entry.is_action_of(creature))?;
+        self.unassigned.push(UnassignedEntry {
+            creature: creature.clone(),
+            bonus_time: entry.bonus_time,
+            delay: 0,
+        });
+        match entry.scheduled {
+            Scheduled::Action(action) => Some(action),
+            Scheduled::Event(..) => unreachable!(),
+        }
+    }
+
+    pub(crate) fn peek_next(&mut self) -> Result<&Scheduled, SchedulerError> {
+        self.unassigned.retain(|UnassignedEntry { creature, .. }|
+                               creature.upgrade().is_some());
 
-        if self.unassigned.len() != 0 {
+        if !self.unassigned.is_empty() {
             return Err(SchedulerError::ActionNotAssigned(
                 self.unassigned[0].creature.clone()))
         }
 
         match self.queue.peek() {
-            Some(&ActionEntry { ref action, .. }) => Ok(action),
+            Some(QueueEntry { scheduled, .. }) => Ok(scheduled),
             None => Err(SchedulerError::QueueIsEmpty),
         }
-        
     }
 
-    /// Returns next action scheduled to apply
-    pub(crate) fn pop_next(&mut self) -> Result<Action, SchedulerError> {
+    /// Returns next action or event scheduled to apply, advancing time to it.
+    /// Actor becomes unassigned keeping its unspent bonus time
+    pub(crate) fn pop_next(&mut self) -> Result<Scheduled, SchedulerError> {
         self.peek_next()?; // all errors must be handled in peek_next()
-        let ActionEntry { action, cost } = self.queue.pop().unwrap();
-
-        let cost = *cost.borrow();
-        let bonus_time = if cost > 0 {
-            for entry in self.queue.iter() {
-                *entry.cost.borrow_mut() -= cost as i32;
-                // TODO: add bonus time when action returned with
-                // negative action times
-            }
-            0
-        } else {
-            -cost
-        } as u32;
-        self.unassigned.push(
-            UnassignedEntry {
-                creature: action.actor().clone(),
-                bonus_time: bonus_time,
-            });
-        Ok(action)
+        let QueueEntry { scheduled, time, bonus_time, .. } = self.queue.pop().unwrap();
+        debug_assert!(time >= self.time, "Entries can't be scheduled in the past");
+        self.time = time;
+        if let Scheduled::Action(ref action) = scheduled {
+            self.unassigned.push(
+                UnassignedEntry {
+                    creature: action.actor().clone(),
+                    bonus_time,
+                    delay: 0,
+                });
+        }
+        Ok(scheduled)
     }
 }
 
@@ -155,8 +264,9 @@ impl Scheduler {
 mod tests {
     use super::*;
     use creatures::Creature;
-    use actions::Action::MockAction;
+    use actions::Action::Mock;
     use std::rc::Rc;
+    use std::cell::RefCell;
 
     fn mock_creature(name: &str) -> Rc<CreatureRef> {
         Rc::new(
@@ -178,9 +288,9 @@ mod tests {
     fn sequential_actions() {
         let creatures = creatures_setup();
         let mut scheduler = Scheduler::new();
-        scheduler.post_action(MockAction(Rc::downgrade(&creatures[0]), 1));
-        scheduler.post_action(MockAction(Rc::downgrade(&creatures[1]), 2));
-        scheduler.post_action(MockAction(Rc::downgrade(&creatures[2]), 3));
+        scheduler.post_action(Mock(Rc::downgrade(&creatures[0]), 1));
+        scheduler.post_action(Mock(Rc::downgrade(&creatures[1]), 2));
+        scheduler.post_action(Mock(Rc::downgrade(&creatures[2]), 3));
 
         let expected: Vec<_> = creatures.iter()
             .take(3)
@@ -192,13 +302,15 @@ mod tests {
         let mut result = vec![];
         loop {
             match scheduler.pop_next() {
-                Ok(action) => {
+                Ok(Scheduled::Action(action)) => {
                     let actor = action.actor().upgrade().unwrap();
                     result.push(actor.borrow().name().to_owned());
                 },
+                Err(SchedulerError::ActionNotAssigned(_)) if result.len() == expected.len() =>
+                    break,
                 Err(SchedulerError::ActionNotAssigned(creature)) =>
-                    scheduler.post_action(MockAction(creature, 5)),
-                Err(SchedulerError::QueueIsEmpty) => break,
+                    scheduler.post_action(Mock(creature, 5)),
+                Ok(Scheduled::Event(..)) | Err(SchedulerError::QueueIsEmpty) => break,
             }
         }
         assert_eq!(result,
@@ -212,10 +324,160 @@ mod tests {
     fn non_assigned() {
         let creatures = creatures_setup();
         let mut scheduler = Scheduler::new();
-        scheduler.post_action(MockAction(Rc::downgrade(&creatures[0]), 1));
-        scheduler.post_action(MockAction(Rc::downgrade(&creatures[1]), 2));
-        scheduler.post_action(MockAction(Rc::downgrade(&creatures[2]), 3));
-        scheduler.pop_next();
+        scheduler.post_action(Mock(Rc::downgrade(&creatures[0]), 1));
+        scheduler.post_action(Mock(Rc::downgrade(&creatures[1]), 2));
+        scheduler.post_action(Mock(Rc::downgrade(&creatures[2]), 3));
+        assert!(scheduler.pop_next().is_ok());
         assert!(scheduler.pop_next().is_err());
     }
+
+    /// Posts the action for the unassigned creature and pops the next one,
+    /// returns its actor index and the time it was applied at
+    fn step(scheduler: &mut Scheduler,
+            creatures: &[Rc<CreatureRef>],
+            cost: &mut dyn FnMut(usize) -> i32) -> (usize, u64) {
+        loop {
+            match scheduler.pop_next() {
+                Ok(Scheduled::Action(action)) => {
+                    let index = creatures.iter()
+                        .position(|creature| action.actor().identical(creature))
+                        .unwrap();
+                    return (index, scheduler.time())
+                },
+                Err(SchedulerError::ActionNotAssigned(creature)) => {
+                    let index = creatures.iter()
+                        .position(|other| creature.identical(other))
+                        .unwrap();
+                    scheduler.post_action(Mock(creature, cost(index)));
+                },
+                Ok(Scheduled::Event(..)) | Err(SchedulerError::QueueIsEmpty) =>
+                    panic!("Only actions are expected"),
+            }
+        }
+    }
+
+    #[test]
+    fn free_actions() {
+        let creatures = creatures_setup();
+        let mut scheduler = Scheduler::new();
+        scheduler.post_action(Mock(Rc::downgrade(&creatures[0]), 0));
+        scheduler.post_action(Mock(Rc::downgrade(&creatures[1]), 100));
+        assert!(scheduler.pop_next().is_ok());
+        assert_eq!(scheduler.time(), 0);
+        // free action doesn't let the creature skip the line
+        scheduler.post_action(Mock(Rc::downgrade(&creatures[0]), 100));
+        match scheduler.pop_next() {
+            Ok(Scheduled::Action(action)) => assert!(action.actor().identical(&creatures[1])),
+            _ => panic!("Action of the other creature must be next"),
+        }
+        assert_eq!(scheduler.time(), 100);
+    }
+
+    #[test]
+    fn refunded_time_carries_over() {
+        let creatures = creatures_setup();
+        let mut scheduler = Scheduler::new();
+        scheduler.add_creature(Rc::downgrade(&creatures[0]));
+        scheduler.post_action(Mock(Rc::downgrade(&creatures[1]), 100));
+        // creature refunds 150, then acts twice for free and pays only 50 of
+        // the fourth action, still going before the other one
+        let mut costs = vec![-150, 50, 50, 100, 100].into_iter();
+        let times: Vec<_> = (0..4)
+            .map(|_| step(&mut scheduler, &creatures[..2], &mut |_| costs.next().unwrap()))
+            .collect();
+        assert_eq!(times, vec![(0, 0), (0, 0), (0, 0), (0, 50)]);
+    }
+
+    /// Linear congruential generator, enough to produce test data
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self, bound: u64) -> u64 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (self.0 >> 33) % bound
+        }
+    }
+
+    #[test]
+    fn actions_follow_their_costs() {
+        for seed in 0..20 {
+            let mut rng = Lcg(seed);
+            let creatures = creatures_setup();
+            let mut scheduler = Scheduler::new();
+            for creature in creatures.iter() {
+                scheduler.add_creature(Rc::downgrade(creature));
+            }
+            // running sum of costs per creature and its running maximum,
+            // which is exactly when the creature's action must be applied
+            let mut sums = vec![0i64; creatures.len()];
+            let mut expected = vec![0i64; creatures.len()];
+            let mut pending = vec![0i64; creatures.len()];
+            let mut last_time = 0;
+            for _ in 0..2000 {
+                let (index, time) = step(&mut scheduler, &creatures, &mut |index| {
+                    let cost = rng.next(300) as i32 - 50;
+                    sums[index] += cost as i64;
+                    pending[index] = ::std::cmp::max(expected[index], sums[index]);
+                    cost
+                });
+                expected[index] = pending[index];
+                assert!(time >= last_time, "Time must never go backwards");
+                assert_eq!(time as i64, expected[index], "Seed {}: time is lost or gained", seed);
+                last_time = time;
+            }
+        }
+    }
+
+    #[test]
+    fn equal_costs_take_turns() {
+        let mut rng = Lcg(42);
+        let creatures = creatures_setup();
+        let mut scheduler = Scheduler::new();
+        for creature in creatures.iter() {
+            scheduler.add_creature(Rc::downgrade(creature));
+        }
+        // k-th action of every creature costs the same, including free ones
+        let costs: Vec<i32> = (0..1000).map(|_| rng.next(200) as i32).collect();
+        let mut counts = vec![0; creatures.len()];
+        let mut round = vec![];
+        for _ in 0..1000 {
+            let (index, _) = step(&mut scheduler, &creatures, &mut |index| {
+                counts[index] += 1;
+                costs[counts[index] - 1]
+            });
+            assert!(!round.contains(&index), "Creature acted twice in one round");
+            round.push(index);
+            if round.len() == creatures.len() {
+                round.clear();
+            }
+        }
+    }
+
+    #[test]
+    fn events_and_cancellation() {
+        let creatures = creatures_setup();
+        let mut scheduler = Scheduler::new();
+        let explosion = TimedEvent::Explosion {
+            position: Position { level: 0, x: 1, y: 1 }, radius: 1, damage: 1 };
+        let cancelled = scheduler.schedule_event(50, TimedEvent::Reinforcements(vec![]));
+        scheduler.schedule_event(150, explosion);
+        scheduler.post_action(Mock(Rc::downgrade(&creatures[0]), 100));
+        scheduler.post_action(Mock(Rc::downgrade(&creatures[1]), 200));
+        assert!(scheduler.cancel_event(cancelled).is_some());
+        assert!(scheduler.cancel_event(cancelled).is_none());
+
+        assert!(scheduler.delay_action(&Rc::downgrade(&creatures[0]), 100));
+        scheduler.remove_creature(&Rc::downgrade(&creatures[1]));
+        match scheduler.pop_next() {
+            Ok(Scheduled::Event(_, TimedEvent::Explosion { .. })) => (),
+            _ => panic!("Explosion must go first"),
+        }
+        assert_eq!(scheduler.time(), 150);
+        assert!(scheduler.cancel_action(&Rc::downgrade(&creatures[0])).is_some());
+        assert!(scheduler.is_unassigned(&Rc::downgrade(&creatures[0])));
+        match scheduler.pop_next() {
+            Err(SchedulerError::ActionNotAssigned(_)) => (),
+            _ => panic!("Creature must decide again"),
+        }
+    }
 }
@@ -0,0 +1,163 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::rc::Weak;
+
+use world::CreatureRef;
+use utils::*;
+use demonicity::Corruption;
+use status::{Status, StatusEffect};
+
+/// What kind of target spell must be aimed at
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Targeting {
+    Caster,
+    Creature,
+    Tile,
+    /// All creatures within radius around the tile
+    Area { radius: usize },
+}
+
+/// Actual target chosen for the spell, must correspond to spell's [`Targeting`]
+#[derive(Clone, Debug)]
+pub enum SpellTarget {
+    Caster,
+    Creature(Weak<CreatureRef>),
+    Tile(Position),
+    Area(Position),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpellEffect {
+    Damage(u32),
+    Heal(u32),
+    /// Moves caster to the target tile
+    Teleport,
+    /// Puts the status effect on the targets
+    Inflict(StatusEffect),
+}
+
+/// Definition of the spell, which can be cast with [`Action::Cast`]
+#[derive(Clone, Debug)]
+pub struct Spell {
+    pub name: String,
+    pub mana_cost: u32,
+    /// Time-points spent on casting
+    pub time_cost: u32,
+    /// Maximal distance from caster to the target
+    pub range: usize,
+    pub targeting: Targeting,
+    pub effect: SpellEffect,
+    /// Minimal corruption of the demon able to cast the spell
+    pub required_corruption: Corruption,
+    /// Demonicity gained by caster, dark spells are the ones with non-zero gain
+    pub demonicity_gain: u32,
+}
+
+impl SpellTarget {
+    pub fn matches(&self, targeting: Targeting) -> bool {
+        matches!((self, targeting),
+                 (&SpellTarget::Caster, Targeting::Caster) |
+                 (&SpellTarget::Creature(_), Targeting::Creature) |
+                 (&SpellTarget::Tile(_), Targeting::Tile) |
+                 (&SpellTarget::Area(_), Targeting::Area { .. }))
+    }
+}
+
+impl Spell {
+    /// All built-in spells
+    pub fn library() -> Vec<Spell> {
+        vec![
+            Spell::infernal_bolt(),
+            Spell::shadow_step(),
+            Spell::dark_mending(),
+            Spell::hellfire(),
+            Spell::torpor(),
+        ]
+    }
+
+    pub fn infernal_bolt() -> Spell {
+        Spell {
+            name: String::from("Infernal Bolt"),
+            mana_cost: 5,
+            time_cost: 100,
+            range: 6,
+            targeting: Targeting::Creature,
+            effect: SpellEffect::Damage(6),
+            required_corruption: Corruption::Dormant,
+            demonicity_gain: 1,
+        }
+    }
+
+    pub fn hellfire() -> Spell {
+        Spell {
+            name: String::from("Hellfire"),
+            mana_cost: 12,
+            time_cost: 150,
+            range: 5,
+            targeting: Targeting::Area { radius: 1 },
+            effect: SpellEffect::Damage(8),
+            required_corruption: Corruption::Ascended,
+            demonicity_gain: 3,
+        }
+    }
+
+    pub fn shadow_step() -> Spell {
+        Spell {
+            name: String::from("Shadow Step"),
+            mana_cost: 8,
+            time_cost: 50,
+            range: 4,
+            targeting: Targeting::Tile,
+            effect: SpellEffect::Teleport,
+            required_corruption: Corruption::Dormant,
+            demonicity_gain: 1,
+        }
+    }
+
+    pub fn torpor() -> Spell {
+        Spell {
+            name: String::from("Torpor"),
+            mana_cost: 6,
+            time_cost: 100,
+            range: 5,
+            targeting: Targeting::Creature,
+            effect: SpellEffect::Inflict(StatusEffect::new(Status::Slowed, 500, 0)),
+            required_corruption: Corruption::Awakened,
+            demonicity_gain: 1,
+        }
+    }
+
+    pub fn dark_mending() -> Spell {
+        Spell {
+            name: String::from("Dark Mending"),
+            mana_cost: 10,
+            time_cost: 200,
+            range: 0,
+            targeting: Targeting::Caster,
+            effect: SpellEffect::Heal(10),
+            required_corruption: Corruption::Awakened,
+            demonicity_gain: 2,
+        }
+    }
+}
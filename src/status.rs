@@ -0,0 +1,118 @@
+/*
+    MIT License
+
+    Copyright (c) 2017 Dan Hawk
+
+    Permission is hereby granted, free of charge, to any person obtaining a copy
+    of this software and associated documentation files (the "Software"), to deal
+    in the Software without restriction, including without limitation the rights
+    to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+    copies of the Software, and to permit persons to whom the Software is
+    furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in all
+    copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+    OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+    SOFTWARE.
+    */
+use std::cmp;
+
+/// Time-points between two ticks of damage over time
+pub const STATUS_TICK: u32 = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    /// Takes damage every tick
+    Poisoned,
+    /// Takes damage every tick
+    Burning,
+    /// Next action is delayed until the stun wears off
+    Stunned,
+    /// Actions take half of the usual time
+    Hasted,
+    /// Actions take twice the usual time
+    Slowed,
+    /// Can't be noticed by hostile creatures
+    Invisible,
+}
+
+impl Status {
+    /// Percent of the usual cost actions take under the status
+    pub fn cost_percent(self) -> u32 {
+        match self {
+            Status::Hasted => 50,
+            Status::Slowed => 200,
+            _ => 100,
+        }
+    }
+}
+
+/// Status applied to the creature for some time, duration is measured in
+/// the same time-points as action costs
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StatusEffect {
+    pub status: Status,
+    /// Time-points left until the effect wears off
+    pub remaining: u32,
+    /// Damage per tick for poison and burning, ignored by others
+    pub strength: u32,
+    /// Time-points passed since the last tick
+    elapsed: u32,
+}
+
+impl StatusEffect {
+    pub fn new(status: Status, duration: u32, strength: u32) -> StatusEffect {
+        StatusEffect { status, remaining: duration, strength, elapsed: 0 }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Combines with the same status applied again, the stronger and longer wins
+    pub(crate) fn merge(&mut self, other: &StatusEffect) {
+        self.remaining = cmp::max(self.remaining, other.remaining);
+        self.strength = cmp::max(self.strength, other.strength);
+    }
+
+    /// Passes the time, returns damage dealt over that time
+    pub(crate) fn tick(&mut self, elapsed: u32) -> u32 {
+        let elapsed = cmp::min(elapsed, self.remaining);
+        self.remaining -= elapsed;
+        self.elapsed += elapsed;
+        let ticks = self.elapsed / STATUS_TICK;
+        self.elapsed %= STATUS_TICK;
+        match self.status {
+            Status::Poisoned | Status::Burning => ticks * self.strength,
+            _ => 0,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poison_ticks() {
+        let mut poison = StatusEffect::new(Status::Poisoned, 350, 2);
+        assert_eq!(poison.tick(50), 0);
+        assert_eq!(poison.tick(200), 4);
+        assert_eq!(poison.tick(1000), 2);
+        assert!(poison.is_over());
+    }
+
+    #[test]
+    fn stronger_status_wins() {
+        let mut burning = StatusEffect::new(Status::Burning, 300, 1);
+        burning.merge(&StatusEffect::new(Status::Burning, 100, 3));
+        assert_eq!((burning.remaining, burning.strength), (300, 3));
+    }
+}
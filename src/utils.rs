@@ -1,7 +1,8 @@
+use std;
 use std::ops::Add;
 use std::rc::{Rc,Weak};
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Position {
     pub level: usize,
     pub x: usize,
@@ -10,9 +11,62 @@ pub struct Position {
 
 
 
-#[derive(Clone, Copy, Debug)]
+impl Position {
+    /// Number of steps between positions on the same level
+    /// (diagonal counts as one step). Positions on different levels are
+    /// infinitely far from each other
+    pub fn distance(&self, other: &Position) -> usize {
+        if self.level != other.level {
+            return usize::MAX
+        }
+        let dx = (self.x as isize - other.x as isize).unsigned_abs();
+        let dy = (self.y as isize - other.y as isize).unsigned_abs();
+        std::cmp::max(dx, dy)
+    }
+}
+
+/// Time-points of the step in straight direction
+const STRAIGHT_STEP_COST: u32 = 100;
+/// Time-points of the diagonal step, it is longer by square root of two
+const DIAGONAL_STEP_COST: u32 = 141;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Direction {
-    Left, Right, Up, Down
+    Left, Right, Up, Down,
+    UpLeft, UpRight, DownLeft, DownRight,
+}
+
+impl Direction {
+    /// All directions creature can move in
+    pub fn all() -> &'static [Direction] {
+        use self::Direction::*;
+        &[Left, Right, Up, Down, UpLeft, UpRight, DownLeft, DownRight]
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        self.components().is_some()
+    }
+
+    /// Vertical and horizontal parts of the diagonal direction
+    pub fn components(self) -> Option<(Direction, Direction)> {
+        use self::Direction::*;
+        match self {
+            UpLeft => Some((Up, Left)),
+            UpRight => Some((Up, Right)),
+            DownLeft => Some((Down, Left)),
+            DownRight => Some((Down, Right)),
+            Left | Right | Up | Down => None,
+        }
+    }
+
+    /// Base time-points of the step in the direction
+    pub fn step_cost(self) -> u32 {
+        if self.is_diagonal() {
+            DIAGONAL_STEP_COST
+        } else {
+            STRAIGHT_STEP_COST
+        }
+    }
 }
 
 impl Add<Direction> for Position {
@@ -25,7 +79,11 @@ impl Add<Direction> for Position {
             Left => (-1, 0),
             Right => (1, 0),
             Up => (0, -1),
-            Down => (0, 1)
+            Down => (0, 1),
+            UpLeft => (-1, -1),
+            UpRight => (1, -1),
+            DownLeft => (-1, 1),
+            DownRight => (1, 1),
         };
 
         let (x, y) = (x + dx, y + dy);
@@ -45,7 +103,7 @@ pub(crate) trait Identical<T> {
 
 impl<T> Identical<Rc<T>> for Rc<T> {
     fn identical(&self, other: &Rc<T>) -> bool {
-        self.as_ref() as *const _ == other.as_ref() as *const _
+        std::ptr::eq(self.as_ref(), other.as_ref())
     }
 }
 
@@ -83,6 +141,22 @@ mod tests {
     use super::*;
     use std::rc::Rc;
 
+    #[test]
+    fn distance_between_positions() {
+        let a = Position { level: 0, x: 1, y: 1 };
+        let b = Position { level: 0, x: 4, y: 3 };
+        assert_eq!(a.distance(&b), 3);
+        assert_eq!(a.distance(&Position { level: 1, ..a }), usize::MAX);
+    }
+
+    #[test]
+    fn diagonal_step() {
+        let position = Position { level: 0, x: 1, y: 1 };
+        assert_eq!(position + Direction::DownRight, Some(Position { level: 0, x: 2, y: 2 }));
+        assert_eq!(position + Direction::UpLeft, Some(Position { level: 0, x: 0, y: 0 }));
+        assert_eq!(Position { x: 0, ..position } + Direction::DownLeft, None);
+    }
+
     #[test]
     fn weak_refs_identical() {
         let a = Rc::new(5);
@@ -21,24 +21,90 @@
     OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
     SOFTWARE.
     */
-use std::cell::RefCell;
+use std::cell::{RefCell, RefMut};
 use std::rc::{Rc, Weak};
+use std::mem;
+use std::collections::HashMap;
 
-use map::tiles::Map;
+use map::tiles::{Map, Corpse, TileType};
+use map::fov::FieldOfView;
+use map::pathfinding::{self, DijkstraMap, Goal, PathCache};
+use map::analysis::{self, LevelReport};
 use map::generators::*;
-use creatures::Creature;
+use creatures::{Creature, CreatureType};
+use demonicity;
+use status::{Status, StatusEffect};
+use actions::{self, Action, ActionError};
+use events::*;
 use utils::*;
-use scheduler::Scheduler;
+use rng::{Rng, RngStreams};
+use scheduler::{Scheduled, Scheduler, SchedulerError};
 
 pub type CreatureRef = RefCell<Creature>;
 pub type MapRef = RefCell<Map>;
 
+/// Chooses next action for creatures other than main character whenever
+/// they have nothing scheduled and no behaviour of their own
+pub trait DecisionSource {
+    /// Returned action must have `creature` as its actor
+    fn decide(&self, world: &World, creature: &Rc<CreatureRef>) -> Action;
+}
+
+/// Decision source that makes every creature just wait
+pub struct Idle;
+
+impl DecisionSource for Idle {
+    fn decide(&self, _world: &World, creature: &Rc<CreatureRef>) -> Action {
+        Action::Wait(Rc::downgrade(creature))
+    }
+}
+
 /// Main entity holding entire game state with levels, creatures, player character etc.
 pub struct World {
     main_character: Rc<CreatureRef>,
     levels: Vec<MapRef>,
     creatures: Vec<Rc<CreatureRef>>,
     scheduler: Scheduler,
+    decision_source: Box<dyn DecisionSource>,
+    events: Vec<Event>,
+    /// Time-points passed since the last demonicity decay
+    decay_timer: u32,
+    path_cache: RefCell<PathCache>,
+    /// Whether diagonal moves past wall corners are allowed
+    corner_cutting: bool,
+    level_generator: Box<dyn MapGenerator>,
+    /// Stairs leading to each other, both directions are stored
+    stairs: HashMap<Position, Position>,
+    seed: u64,
+    rng: RngStreams,
+}
+
+/// Seed of the world created with [`World::new`]
+const DEFAULT_SEED: u64 = 0;
+
+/// Levels generated in a row with unreachable stairs before giving up
+const GENERATION_ATTEMPTS: u32 = 10;
+
+/// Generates the level and finds where creatures enter it, near given
+/// coordinates. Levels whose stairs can't be reached from there are thrown
+/// away and generated anew, `None` if all the attempts failed
+fn generate_level(generator: &dyn MapGenerator, rng: &mut Rng, level: usize, x: usize, y: usize)
+                  -> Option<(Map, Position)> {
+    for _ in 0..GENERATION_ATTEMPTS {
+        let map = generator.generate(rng);
+        if let Some(spawn) = analysis::spawn_point(&map, level, x, y) {
+            if LevelReport::new(&map, spawn).is_valid() {
+                return Some((map, spawn))
+            }
+        }
+    }
+    None
+}
+
+impl Default for World {
+    fn default() -> World {
+        World::new()
+    }
 }
 
 impl World {
@@ -46,22 +112,59 @@ impl World {
     /// Creates new world with generating levels for it
     pub fn new() -> World {
         // current logic is stub, used only for debugging and testing
-        let level = SimpleBoxGenerator::new(20, 20).generate();
+        World::with_seed(DEFAULT_SEED)
+    }
+
+    pub fn with_seed(seed: u64) -> World {
+        World::with_generator(Box::new(SimpleBoxGenerator::new(20, 20)), seed)
+            .expect("Box level always has its stairs reachable")
+    }
+
+    /// Creates new world, its levels are generated with the generator the
+    /// first time someone gets there. Everything random in the world comes
+    /// from the seed, so the same seed and actions replay the same game.
+    /// None if the generator keeps making first levels with unreachable stairs
+    pub fn with_generator(level_generator: Box<dyn MapGenerator>, seed: u64) -> Option<World> {
+        let rng = RngStreams::new(seed);
+        let (level, spawn) = generate_level(&*level_generator, &mut rng.map.borrow_mut(), 0, 5, 5)?;
 
         let main_character = Rc::new(RefCell::new(Creature::demon(
             String::from("Very Evil Demon"),
             30,
             30,
-            Position { level: 0, x: 5, y: 5 },
+            spawn,
             0)));
         let mut world = World {
             main_character: main_character.clone(),
             levels: vec![RefCell::new(level)],
             creatures: vec![],
-            scheduler: Scheduler::new()
+            scheduler: Scheduler::new(),
+            decision_source: Box::new(Idle),
+            events: vec![],
+            decay_timer: 0,
+            path_cache: RefCell::new(PathCache::default()),
+            corner_cutting: true,
+            level_generator,
+            stairs: HashMap::new(),
+            seed,
+            rng,
         };
         world.add_creature(main_character);
-        world
+        world.place_down_stairs(spawn);
+        Some(world)
+    }
+
+    /// Seed the world was created with, enough to reproduce it
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub(crate) fn combat_rng(&self) -> RefMut<'_, Rng> {
+        self.rng.combat.borrow_mut()
+    }
+
+    pub(crate) fn ai_rng(&self) -> RefMut<'_, Rng> {
+        self.rng.ai.borrow_mut()
     }
 
     pub fn get_level(&self, level: usize) -> &MapRef {
@@ -72,17 +175,416 @@ impl World {
         Rc::downgrade(&self.main_character)
     }
 
-    pub fn turn(&mut self) {
-        // TODO: stub, need to return some info about
-        //       action (or, probably result)
-        //       And do multiple actions until main character not next
-        unimplemented!();
+    pub fn corner_cutting(&self) -> bool {
+        self.corner_cutting
+    }
+
+    /// Allows or forbids diagonal moves past wall corners
+    pub fn set_corner_cutting(&mut self, allowed: bool) {
+        self.corner_cutting = allowed;
+    }
+
+    /// Shortest path between positions on the same level
+    pub fn find_path(&self, from: Position, to: Position, avoid_creatures: bool)
+                     -> Option<Vec<Direction>> {
+        let map = self.levels.get(from.level)?.borrow();
+        pathfinding::find_path(&map, from, to, avoid_creatures, self.corner_cutting)
+    }
+
+    /// Dijkstra map of the level leading to the goal, cached until goal
+    /// moves or level is changed. None if there is no such level
+    pub fn dijkstra_map(&self, level: usize, goal: Goal) -> Option<Rc<DijkstraMap>> {
+        let map = self.levels.get(level)?.borrow();
+        let goals = match goal {
+            Goal::MainCharacter => {
+                let main_character = self.main_character.borrow();
+                Some(main_character.position())
+                    .filter(|position| main_character.is_alive() && position.level == level)
+                    .into_iter()
+                    .collect()
+            },
+            Goal::Exits => map.tiles()
+                .filter(|&(_, _, tile)| matches!(tile.tile_type, TileType::Stairs { .. }))
+                .map(|(x, y, _)| Position { level, x, y })
+                .collect(),
+        };
+        Some(self.path_cache.borrow_mut().get(&map, level, goal, goals, self.corner_cutting))
+    }
+
+    pub fn levels_count(&self) -> usize {
+        self.levels.len()
     }
 
-    fn add_creature(&mut self, creature: Rc<CreatureRef>) {
+    /// Where the stairs at the position lead, `None` if they are not
+    /// linked yet (the level below has never been visited)
+    pub fn stairs_destination(&self, position: Position) -> Option<Position> {
+        self.stairs.get(&position).cloned()
+    }
+
+    /// Returns where the stairs lead generating the next level if it is
+    /// the first time someone goes down
+    pub(crate) fn follow_stairs(&mut self, position: Position) -> Result<Position, ActionError> {
+        if let Some(destination) = self.stairs_destination(position) {
+            return Ok(destination)
+        }
+        // generated level may have several stairs down, the ones followed
+        // later lead to the existing level
+        let level = position.level + 1;
+        let generated = level == self.levels.len();
+        let arrival = if generated {
+            let (map, arrival) = generate_level(&*self.level_generator, &mut self.rng.map.borrow_mut(),
+                                                level, position.x, position.y)
+                .ok_or(ActionError::GenerationFailed(level))?;
+            self.levels.push(RefCell::new(map));
+            arrival
+        } else {
+            self.nearest_free_tile(level, position.x, position.y)
+        };
+        self.levels[level].borrow_mut().0[arrival.x][arrival.y].tile_type =
+            TileType::Stairs { down: false };
+        self.tiles_changed(level);
+        self.stairs.insert(position, arrival);
+        self.stairs.insert(arrival, position);
+        if generated {
+            self.place_down_stairs(arrival);
+            self.log_event(Event::LevelGenerated(level));
+        }
+        Ok(arrival)
+    }
+
+    /// Free ground tile of the level closest to given coordinates
+    fn nearest_free_tile(&self, level: usize, x: usize, y: usize) -> Position {
+        let origin = Position { level, x, y };
+        self.levels[level].borrow()
+            .tiles()
+            .filter(|&(_, _, tile)| matches!(tile.tile_type, TileType::Ground) &&
+                    tile.creature.is_none())
+            .map(|(x, y, _)| Position { level, x, y })
+            .min_by_key(|position| position.distance(&origin))
+            .expect("Level must have some free ground")
+    }
+
+    /// Puts stairs down to the ground tile farthest from where creatures
+    /// get to the level, unless the generator has already put some
+    fn place_down_stairs(&mut self, entrance: Position) {
+        let level = entrance.level;
+        let stairs = {
+            let map = self.levels[level].borrow();
+            if map.tiles().any(|(_, _, tile)| matches!(tile.tile_type, TileType::Stairs { down: true })) {
+                return
+            }
+            let distances = DijkstraMap::new(&map, level, &[entrance], self.corner_cutting);
+            map.tiles()
+                .filter(|&(_, _, tile)| matches!(tile.tile_type, TileType::Ground))
+                .map(|(x, y, _)| Position { level, x, y })
+                .max_by_key(|&position| distances.distance(position))
+        };
+        if let Some(stairs) = stairs {
+            self.levels[level].borrow_mut().0[stairs.x][stairs.y].tile_type =
+                TileType::Stairs { down: true };
+            self.tiles_changed(level);
+        }
+    }
+
+    /// Must be called after tiles of the level were changed from outside,
+    /// drops cached paths and lets creatures on the level see the change
+    pub fn tiles_changed(&self, level: usize) {
+        self.path_cache.borrow_mut().invalidate(level);
+        for creature in self.creatures.iter()
+            .filter(|creature| creature.borrow().position().level == level) {
+            self.update_memory(creature);
+        }
+    }
+
+    /// Replaces source of decisions for creatures other than main character
+    /// that don't have behaviour
+    pub fn set_decision_source(&mut self, decision_source: Box<dyn DecisionSource>) {
+        self.decision_source = decision_source;
+    }
+
+    /// Schedules the event to happen after `delay` time-points
+    pub fn schedule_event(&mut self, delay: u32, event: TimedEvent) -> TimedEventId {
+        self.scheduler.schedule_event(delay, event)
+    }
+
+    /// Returns the event back if it hasn't happened yet
+    pub fn cancel_event(&mut self, id: TimedEventId) -> Option<TimedEvent> {
+        self.scheduler.cancel_event(id)
+    }
+
+    /// Takes back pending action of the creature, it will be asked to
+    /// decide again
+    pub fn interrupt(&mut self, creature: &Weak<CreatureRef>) -> Option<Action> {
+        self.scheduler.cancel_action(creature)
+    }
+
+    /// Places new creature into the world at its position, tile must be
+    /// passable and free
+    pub fn spawn_creature(&mut self, creature: Creature)
+                          -> Result<Weak<CreatureRef>, ActionError> {
+        let position = creature.position();
+        if position.level >= self.levels.len() {
+            return Err(ActionError::NoSuchLevel(position.level))
+        }
+        {
+            let map = self.levels[position.level].borrow();
+            let Map(ref tiles) = *map;
+            if tiles.len() <= position.x || tiles[0].len() <= position.y {
+                return Err(ActionError::OutOfBounds {
+                    position: Some(position),
+                    width: tiles.len(),
+                    height: tiles[0].len(),
+                })
+            }
+            let tile = map.get_by_pos(position);
+            if let Some(ref creature) = tile.creature {
+                return Err(ActionError::TileIsOccupied(creature.clone()))
+            }
+            if !tile.is_passable() {
+                return Err(ActionError::TileIsImpassable(position))
+            }
+        }
+        let creature = Rc::new(RefCell::new(creature));
+        let weak = Rc::downgrade(&creature);
+        self.add_creature(creature);
+        Ok(weak)
+    }
+
+    /// Schedules next action of the main character. Action is rejected if
+    /// it is not valid right now or if it belongs to some other creature.
+    pub fn submit_player_action(&mut self, action: Action) -> actions::Result {
+        if !self.main_character.identical(action.actor()) {
+            return Err(ActionError::NotMainCharacter)
+        }
+        if !self.scheduler.is_unassigned(action.actor()) {
+            return Err(ActionError::ActionAlreadyAssigned)
+        }
+        action.is_valid(self)?;
+        self.scheduler.post_action(action);
+        Ok(())
+    }
+
+    /// Applies scheduled actions one by one until main character needs input
+    /// (or nothing is scheduled at all). Creatures without assigned action
+    /// are asked for it through decision source.
+    pub fn turn(&mut self) -> TurnResult {
+        let status = loop {
+            if !self.main_character.borrow().is_alive() {
+                break TurnStatus::MainCharacterDied;
+            }
+            let time = self.scheduler.time();
+            let next = self.scheduler.pop_next();
+            let elapsed = self.scheduler.time() - time;
+            self.advance_time(elapsed as u32);
+            match next {
+                Ok(Scheduled::Event(_, event)) => self.fire_event(event),
+                Ok(Scheduled::Action(action)) => {
+                    // consequences of the action are logged while applying,
+                    // but must follow the action itself
+                    let index = self.events.len();
+                    let result = action.is_valid(self)
+                        .and_then(|_| action.apply(self));
+                    self.events.insert(index, match result {
+                        Ok(()) => Event::ActionCommitted(action),
+                        Err(error) => Event::ActionFailed(action, error),
+                    });
+                },
+                Err(SchedulerError::ActionNotAssigned(creature)) => {
+                    if self.main_character.identical(&creature) {
+                        break TurnStatus::AwaitingInput;
+                    }
+                    // dead creatures are dropped by scheduler before being reported
+                    let creature = creature.upgrade().unwrap();
+                    let behaviour = creature.borrow().behaviour();
+                    let action = match behaviour {
+                        Some(behaviour) => behaviour.next_action(self, &creature),
+                        None => self.decision_source.decide(self, &creature),
+                    };
+                    self.scheduler.post_action(action);
+                },
+                Err(SchedulerError::QueueIsEmpty) => break TurnStatus::QueueIsEmpty,
+            }
+        };
+        TurnResult { events: mem::take(&mut self.events), status }
+    }
+
+    fn fire_event(&mut self, event: TimedEvent) {
+        match event {
+            TimedEvent::Explosion { position, radius, damage } => {
+                self.log_event(Event::Exploded { position, radius });
+                for creature in self.creatures_around(position, radius) {
+                    self.damage_creature(&creature, damage, None);
+                }
+            },
+            TimedEvent::Reinforcements(creatures) =>
+                for creature in creatures {
+                    if let Ok(creature) = self.spawn_creature(creature) {
+                        self.log_event(Event::Spawned(creature));
+                    }
+                },
+        }
+    }
+
+    /// Living creatures standing not farther than radius from the position
+    pub(crate) fn creatures_around(&self, position: Position, radius: usize)
+                                   -> Vec<Rc<CreatureRef>> {
+        self.creatures.iter()
+            .filter(|creature| {
+                let creature = creature.borrow();
+                creature.is_alive() && creature.position().distance(&position) <= radius
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Moves creature to the new position updating tiles. Position must be
+    /// checked beforehand
+    pub(crate) fn relocate_creature(&mut self, creature: &Rc<CreatureRef>, position: Position) {
+        let old = creature.borrow().position();
+        self.levels[old.level].borrow_mut().0[old.x][old.y].creature = None;
+        self.levels[position.level].borrow_mut().0[position.x][position.y].creature =
+            Some(Rc::downgrade(creature));
+        creature.borrow_mut().set_position(position);
+        self.update_memory(creature);
+    }
+
+    /// Lets creature remember everything it sees from its position
+    fn update_memory(&self, creature: &Rc<CreatureRef>) {
+        let mut creature = creature.borrow_mut();
+        let map = self.levels[creature.position().level].borrow();
+        let fov = FieldOfView::new(&map, creature.position(), creature.sight());
+        creature.memory_mut().update(&map, &fov);
+    }
+
+    /// Deals damage to creature killing it if nothing left. Faith of the
+    /// humans protects them from demons, and demon gains demonicity for
+    /// every human killed
+    pub(crate) fn damage_creature(&mut self,
+                                  creature: &Rc<CreatureRef>,
+                                  damage: u32,
+                                  attacker: Option<&Rc<CreatureRef>>) {
+        let demonic = attacker.is_some_and(|attacker| attacker.borrow().corruption().is_some());
+        let damage = if demonic {
+            creature.borrow().resist_demonic(damage)
+        } else {
+            damage
+        };
+        let damage = creature.borrow_mut().take_damage(damage);
+        self.log_event(Event::Damaged { creature: Rc::downgrade(creature), amount: damage });
+        if creature.borrow().is_alive() {
+            return
+        }
+        self.kill_creature(creature);
+        let human_killed = match *creature.borrow().creature_type() {
+            CreatureType::Human { .. } => true,
+            CreatureType::Demon { .. } => false,
+        };
+        if let (true, Some(attacker)) = (human_killed, attacker) {
+            self.change_demonicity(attacker, demonicity::HUMAN_KILL_GAIN as i32);
+        }
+    }
+
+    /// Changes demonicity of the demon logging the changes
+    pub(crate) fn change_demonicity(&mut self, creature: &Rc<CreatureRef>, delta: i32) {
+        let before = creature.borrow().demonicity();
+        let transition = creature.borrow_mut().change_demonicity(delta);
+        let demonicity = creature.borrow().demonicity();
+        if let Some(demonicity) = demonicity.filter(|&demonicity| Some(demonicity) != before) {
+            self.log_event(Event::DemonicityChanged {
+                creature: Rc::downgrade(creature),
+                demonicity,
+            });
+        }
+        if let Some((from, to)) = transition {
+            self.log_event(Event::CorruptionChanged {
+                creature: Rc::downgrade(creature),
+                from,
+                to,
+            });
+        }
+    }
+
+    /// Applies everything that depends on time passing
+    fn advance_time(&mut self, elapsed: u32) {
+        if elapsed == 0 {
+            return
+        }
+        let affected: Vec<_> = self.creatures.iter()
+            .filter(|creature| !creature.borrow().statuses().is_empty())
+            .cloned()
+            .collect();
+        for creature in affected {
+            let (damage, expired) = creature.borrow_mut().tick_statuses(elapsed);
+            for status in expired {
+                self.log_event(Event::StatusExpired { creature: Rc::downgrade(&creature), status });
+            }
+            if damage > 0 && creature.borrow().is_alive() {
+                self.damage_creature(&creature, damage, None);
+            }
+        }
+
+        self.decay_timer += elapsed;
+        while self.decay_timer >= demonicity::DECAY_PERIOD {
+            self.decay_timer -= demonicity::DECAY_PERIOD;
+            let corrupted: Vec<_> = self.creatures.iter()
+                .filter(|creature| creature.borrow().demonicity()
+                        .is_some_and(|demonicity| demonicity > 0))
+                .cloned()
+                .collect();
+            for creature in corrupted {
+                self.change_demonicity(&creature, -1);
+            }
+        }
+    }
+
+    /// Stun postpones the next action of the creature by the time it lasts,
+    /// stunning again postpones it further by as much as the stun got longer
+    pub(crate) fn apply_status(&mut self, creature: &Rc<CreatureRef>, effect: StatusEffect) {
+        let before = creature.borrow().status_remaining(effect.status);
+        let is_new = creature.borrow_mut().add_status(effect);
+        if effect.status == Status::Stunned {
+            let extended = creature.borrow().status_remaining(Status::Stunned) - before;
+            self.scheduler.delay_action(&Rc::downgrade(creature), extended);
+        }
+        if is_new {
+            self.log_event(Event::StatusApplied {
+                creature: Rc::downgrade(creature),
+                status: effect.status,
+            });
+        }
+    }
+
+    pub(crate) fn log_event(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    /// Removes creature from the world and its tile leaving corpse instead.
+    /// Main character is kept referenced to let the game know it is dead.
+    pub(crate) fn kill_creature(&mut self, creature: &Rc<CreatureRef>) {
+        let (name, position) = {
+            let creature = creature.borrow();
+            (creature.name().clone(), creature.position())
+        };
+        {
+            let Map(ref mut map) = *self.levels[position.level].borrow_mut();
+            let tile = &mut map[position.x][position.y];
+            if tile.creature.as_ref().is_some_and(|other| other.identical(creature)) {
+                tile.creature = None;
+            }
+            tile.corpses.push(Corpse { name: name.clone() });
+            tile.items.extend(creature.borrow_mut().drop_everything());
+        }
+        self.creatures.retain(|other| !other.identical(creature));
+        self.scheduler.remove_creature(&Rc::downgrade(creature));
+        self.log_event(Event::Died { name, position });
+    }
+
+    pub(crate) fn add_creature(&mut self, creature: Rc<CreatureRef>) {
         let position = creature.borrow().position();
-        let Map(ref mut map) = *self.levels[position.level].borrow_mut();
-        map[position.x][position.y].creature = Some(Rc::downgrade(&creature));
+        self.levels[position.level].borrow_mut().0[position.x][position.y].creature =
+            Some(Rc::downgrade(&creature));
+        self.update_memory(&creature);
+        self.scheduler.add_creature(Rc::downgrade(&creature));
         self.creatures.push(creature);
     }
 }
@@ -90,10 +592,377 @@ impl World {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::cell::Cell;
+    use std::cmp;
+    use demonicity::Corruption;
+    use creatures::Profession;
+    use map::tiles::TileType;
+    use map::memory::RememberedTile;
 
     #[test]
     fn creating_new_world() {
         // World created without panicking
         World::new();
     }
+
+    #[test]
+    fn turn_waits_for_main_character() {
+        let mut world = World::new();
+        let result = world.turn();
+        assert_eq!(result.status, TurnStatus::AwaitingInput);
+        assert!(result.events.is_empty());
+    }
+
+    #[test]
+    fn submitting_player_action() {
+        let mut world = World::new();
+        let position = world.main_character.borrow().position();
+        world.submit_player_action(Action::Move(world.main_character(), Direction::Right))
+            .unwrap();
+        match world.submit_player_action(Action::Wait(world.main_character())) {
+            Err(ActionError::ActionAlreadyAssigned) => (),
+            _ => panic!("Second action must be rejected"),
+        }
+
+        world.turn();
+        assert_eq!(world.main_character.borrow().position(),
+                   (position + Direction::Right).unwrap());
+    }
+
+    #[test]
+    fn submitting_invalid_player_action() {
+        let mut world = World::new();
+        world.main_character.borrow_mut().set_position(Position { level: 0, x: 1, y: 1 });
+        match world.submit_player_action(Action::Move(world.main_character(), Direction::Left)) {
+            Err(ActionError::TileIsImpassable(_)) => (),
+            _ => panic!("Move into the wall must be rejected"),
+        }
+    }
+
+    #[test]
+    fn main_character_remembers_level() {
+        let mut world = World::new();
+        let position = world.main_character.borrow().position();
+        assert!(world.main_character.borrow().memory().get(position).is_some());
+
+        world.submit_player_action(Action::Move(world.main_character(), Direction::Right))
+            .unwrap();
+        world.turn();
+        let memory = world.main_character.borrow();
+        let remembered = memory.memory().get(position).unwrap();
+        assert!(!remembered.had_creature, "Tile was seen again after moving away");
+    }
+
+    #[test]
+    fn going_downstairs_and_back() {
+        let mut world = World::new();
+        let stairs = world.get_level(0).borrow().tiles()
+            .find(|&(_, _, tile)| matches!(tile.tile_type, TileType::Stairs { down: true }))
+            .map(|(x, y, _)| Position { level: 0, x, y })
+            .unwrap();
+        assert!(world.stairs_destination(stairs).is_none());
+        world.relocate_creature(&world.main_character.clone(), stairs);
+
+        world.submit_player_action(Action::Descend(world.main_character())).unwrap();
+        world.turn();
+        assert_eq!(world.levels_count(), 2);
+        let arrival = world.main_character.borrow().position();
+        assert_eq!(arrival.level, 1);
+        assert_eq!(world.stairs_destination(stairs), Some(arrival));
+        assert!(world.get_level(0).borrow().get_by_pos(stairs).creature.is_none());
+
+        world.submit_player_action(Action::Ascend(world.main_character())).unwrap();
+        world.turn();
+        assert_eq!(world.main_character.borrow().position(), stairs);
+        assert_eq!(world.levels_count(), 2);
+    }
+
+    #[test]
+    fn spawning_humans() {
+        let mut world = World::new();
+        let jailer = Creature::human(String::from("Jailer"), 20, 0,
+                                     Position { level: 0, x: 3, y: 3 }, Profession::Jailer);
+        assert!(world.spawn_creature(jailer).is_ok());
+        let guard = Creature::human(String::from("Guard"), 20, 0,
+                                    Position { level: 0, x: 0, y: 3 }, Profession::Guard);
+        match world.spawn_creature(guard) {
+            Err(ActionError::TileIsImpassable(_)) => (),
+            _ => panic!("Creature can't be spawned inside the wall"),
+        }
+        let jailer = Creature::human(String::from("Jailer"), 20, 0,
+                                     Position { level: 1, x: 3, y: 3 }, Profession::Jailer);
+        match world.spawn_creature(jailer) {
+            Err(ActionError::NoSuchLevel(1)) => (),
+            _ => panic!("Level below is not generated yet"),
+        }
+    }
+
+    #[test]
+    fn path_from_invalid_position() {
+        let world = World::new();
+        let to = Position { level: 0, x: 3, y: 3 };
+        assert!(world.find_path(Position { level: 0, x: 5, y: 5 }, to, false).is_some());
+        assert!(world.find_path(Position { level: 0, x: 50, y: 5 }, to, false).is_none());
+        let below = Position { level: 1, x: 3, y: 3 };
+        assert!(world.find_path(Position { level: 1, x: 5, y: 5 }, below, false).is_none());
+        assert!(world.dijkstra_map(0, Goal::Exits).is_some());
+        assert!(world.dijkstra_map(1, Goal::Exits).is_none());
+    }
+
+    #[test]
+    fn changed_tiles_are_remembered() {
+        let world = World::new();
+        let position = world.main_character.borrow().position();
+        let door = Position { x: position.x + 2, ..position };
+        world.get_level(0).borrow_mut().0[door.x][door.y].tile_type =
+            TileType::Door { closed: true, locked: false, lock: None };
+        world.tiles_changed(0);
+        match world.main_character.borrow().memory().get(door) {
+            Some(&RememberedTile { tile_type: TileType::Door { closed: true, .. }, .. }) => (),
+            _ => panic!("Main character must see the door appear"),
+        };
+    }
+
+    #[test]
+    fn unchanged_demonicity_not_reported() {
+        let mut world = World::new();
+        let main = world.main_character.clone();
+        world.change_demonicity(&main, -5);
+        assert!(!world.events.iter().any(|event| matches!(*event, Event::DemonicityChanged { .. })));
+        world.change_demonicity(&main, 5);
+        assert!(world.events.iter().any(|event| matches!(*event, Event::DemonicityChanged { .. })));
+    }
+
+    #[test]
+    fn demonicity_decays() {
+        let mut world = World::new();
+        let demon = Rc::new(RefCell::new(Creature::demon(
+            String::from("Fallen"), 10, 10, Position { level: 0, x: 7, y: 7 }, 25)));
+        world.add_creature(demon.clone());
+
+        world.advance_time(demonicity::DECAY_PERIOD);
+        assert_eq!(demon.borrow().demonicity(), Some(24));
+        assert!(world.events.iter().any(|event| match *event {
+            Event::CorruptionChanged { to, .. } => to == Corruption::Dormant,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn poison_ticks_with_time() {
+        let mut world = World::new();
+        let main = world.main_character.clone();
+        let health = main.borrow().health().current();
+        world.apply_status(&main, StatusEffect::new(Status::Poisoned, 250, 1));
+
+        world.advance_time(100);
+        assert_eq!(main.borrow().health().current(), health - 1);
+        world.advance_time(200);
+        assert_eq!(main.borrow().health().current(), health - 2);
+        assert!(main.borrow().statuses().is_empty());
+        assert!(world.events.iter().any(|event| match *event {
+            Event::StatusExpired { status, .. } => status == Status::Poisoned,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn haste_gives_more_actions() {
+        let mut world = World::new();
+        let demon = |y| Rc::new(RefCell::new(Creature::demon(
+            String::from("Lesser Demon"), 10, 10, Position { level: 0, x: 7, y }, 0)));
+        let (hasted, normal) = (demon(7), demon(8));
+        world.add_creature(hasted.clone());
+        world.add_creature(normal.clone());
+        world.apply_status(&hasted, StatusEffect::new(Status::Hasted, 10000, 0));
+        world.scheduler.post_action(Action::Mock(world.main_character(), 1000));
+
+        let result = world.turn();
+        let count = |creature: &Rc<CreatureRef>| result.events.iter()
+            .filter(|event| match **event {
+                Event::ActionCommitted(Action::Wait(ref actor)) => actor.identical(creature),
+                _ => false,
+            })
+            .count();
+        assert!(count(&hasted) >= 2 * count(&normal) - 1);
+        assert!(count(&normal) >= 9);
+    }
+
+    #[test]
+    fn timed_events() {
+        let mut world = World::new();
+        let main = world.main_character.clone();
+        let position = main.borrow().position();
+        let health = main.borrow().health().current();
+        let guard = Creature::human(String::from("Guard"), 10, 0,
+                                    Position { level: 0, x: 8, y: 8 }, Profession::Guard);
+        world.schedule_event(50, TimedEvent::Reinforcements(vec![guard]));
+        let explosion = world.schedule_event(
+            80, TimedEvent::Explosion { position, radius: 1, damage: 3 });
+        world.schedule_event(90, TimedEvent::Explosion { position, radius: 1, damage: 2 });
+        assert!(world.cancel_event(explosion).is_some());
+        world.submit_player_action(Action::Mock(world.main_character(), 100)).unwrap();
+
+        let result = world.turn();
+        assert_eq!(main.borrow().health().current(), health - 2);
+        assert!(result.events.iter().any(|event| matches!(*event, Event::Spawned(_))));
+        assert_eq!(world.creatures.len(), 2);
+    }
+
+    #[test]
+    fn reinforcements_to_missing_level() {
+        let mut world = World::new();
+        let guard = Creature::human(String::from("Guard"), 10, 0,
+                                    Position { level: 3, x: 8, y: 8 }, Profession::Guard);
+        world.schedule_event(50, TimedEvent::Reinforcements(vec![guard]));
+        world.submit_player_action(Action::Mock(world.main_character(), 100)).unwrap();
+
+        let result = world.turn();
+        assert!(!result.events.iter().any(|event| matches!(*event, Event::Spawned(_))));
+        assert_eq!(world.creatures.len(), 1);
+    }
+
+    #[test]
+    fn killed_creature_leaves_queue() {
+        let mut world = World::new();
+        let demon = Rc::new(RefCell::new(Creature::demon(
+            String::from("Lesser Demon"), 10, 10, Position { level: 0, x: 7, y: 7 }, 0)));
+        world.add_creature(demon.clone());
+        world.scheduler.post_action(Action::Wait(Rc::downgrade(&demon)));
+        world.kill_creature(&demon);
+        assert!(world.interrupt(&Rc::downgrade(&demon)).is_none());
+    }
+
+    #[test]
+    fn stun_delays_pending_action() {
+        let mut world = World::new();
+        let main = world.main_character.clone();
+        world.submit_player_action(Action::Wait(world.main_character())).unwrap();
+        world.apply_status(&main, StatusEffect::new(Status::Stunned, 200, 0));
+        world.turn();
+        assert_eq!(world.scheduler.time(), 300);
+    }
+
+    #[test]
+    fn stunning_twice_extends_delay() {
+        let mut world = World::new();
+        let main = world.main_character.clone();
+        world.apply_status(&main, StatusEffect::new(Status::Stunned, 200, 0));
+        world.apply_status(&main, StatusEffect::new(Status::Stunned, 300, 0));
+        world.apply_status(&main, StatusEffect::new(Status::Stunned, 100, 0));
+        world.submit_player_action(Action::Wait(world.main_character())).unwrap();
+        world.turn();
+        assert_eq!(world.scheduler.time(), 400);
+
+        world.submit_player_action(Action::Wait(world.main_character())).unwrap();
+        world.apply_status(&main, StatusEffect::new(Status::Stunned, 100, 0));
+        world.apply_status(&main, StatusEffect::new(Status::Stunned, 150, 0));
+        world.turn();
+        assert_eq!(world.scheduler.time(), 650);
+    }
+
+    #[test]
+    fn same_seed_same_rolls() {
+        let (first, second) = (World::with_seed(5), World::with_seed(5));
+        assert_eq!(first.seed(), 5);
+        let rolls = |world: &World| (0..10).map(|_| world.combat_rng().range(0, 100))
+            .collect::<Vec<_>>();
+        assert_eq!(rolls(&first), rolls(&second));
+        assert_ne!(rolls(&first), rolls(&World::with_seed(6)));
+    }
+
+    #[test]
+    fn spawning_on_generated_level() {
+        let world = World::with_generator(Box::new(BspGenerator::new(40, 30)), 3).unwrap();
+        let position = world.main_character.borrow().position();
+        assert!(world.get_level(0).borrow().get_by_pos(position).is_passable());
+    }
+
+    /// Gives the levels in order, the last one over and over
+    struct Fixed(Vec<Map>, Cell<usize>);
+
+    impl Fixed {
+        fn new(maps: Vec<Map>) -> Fixed {
+            Fixed(maps, Cell::new(0))
+        }
+    }
+
+    impl MapGenerator for Fixed {
+        fn generate(&self, _rng: &mut Rng) -> Map {
+            let index = cmp::min(self.1.get(), self.0.len() - 1);
+            self.1.set(index + 1);
+            self.0[index].clone()
+        }
+    }
+
+    fn broken_level() -> Map {
+        Map::from_ascii("
+            ##########
+            #........#
+            #........#
+            ##########
+            #.>#######
+            ##########").unwrap()
+    }
+
+    #[test]
+    fn spawn_avoids_closed_pockets() {
+        let map = Map::from_ascii("
+            ##########
+            #........#
+            #........#
+            #######..#
+            #...#....#
+            #...#....#
+            #...#....#
+            ##########").unwrap();
+        let world = World::with_generator(Box::new(Fixed::new(vec![map])), 0).unwrap();
+        let position = world.main_character.borrow().position();
+        assert_eq!((position.x, position.y), (5, 5));
+
+        let report = LevelReport::new(&world.get_level(0).borrow(), position);
+        assert!(report.is_valid());
+        assert_eq!(report.exit_distances.len(), 1);
+    }
+
+    #[test]
+    fn unreachable_stairs_are_rejected() {
+        assert!(World::with_generator(Box::new(Fixed::new(vec![broken_level()])), 0).is_none());
+    }
+
+    #[test]
+    fn stairs_to_broken_level() {
+        let generator = Fixed::new(vec![SimpleBoxGenerator::new(20, 20).generate(&mut Rng::new(0)),
+                                        broken_level()]);
+        let mut world = World::with_generator(Box::new(generator), 0).unwrap();
+        let stairs = world.get_level(0).borrow().tiles()
+            .find(|&(_, _, tile)| matches!(tile.tile_type, TileType::Stairs { down: true }))
+            .map(|(x, y, _)| Position { level: 0, x, y })
+            .unwrap();
+        let main = world.main_character.clone();
+        world.relocate_creature(&main, stairs);
+
+        match Action::Descend(world.main_character()).apply(&mut world) {
+            Err(ActionError::GenerationFailed(1)) => (),
+            _ => panic!("Level with unreachable stairs must not be entered"),
+        }
+        assert_eq!(world.levels_count(), 1);
+        assert_eq!(main.borrow().position(), stairs);
+    }
+
+    #[test]
+    fn turn_asks_decision_source() {
+        let mut world = World::new();
+        let demon = Rc::new(RefCell::new(Creature::demon(
+            String::from("Lesser Demon"), 10, 10, Position { level: 0, x: 7, y: 7 }, 0)));
+        world.add_creature(demon.clone());
+        world.scheduler.post_action(Action::Mock(world.main_character(), 150));
+
+        let result = world.turn();
+        assert_eq!(result.status, TurnStatus::AwaitingInput);
+        assert!(result.events.iter().any(|event| match *event {
+            Event::ActionCommitted(Action::Wait(ref actor)) => actor.identical(&demon),
+            _ => false,
+        }));
+    }
 }